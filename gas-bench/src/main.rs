@@ -0,0 +1,225 @@
+//! Gas used by the reclaim-xion wasm in cosmwasm-vm, across witness counts and claim sizes.
+//!
+//! cosmwasm-vm only accepts MVP wasm, without the bulk memory and sign extension operations
+//! recent compilers emit by default. The optimizer image builds such a wasm, without it
+//! build one from the repository root with a nightly toolchain and run the benchmark:
+//!
+//!   RUSTFLAGS="-C target-cpu=mvp -C link-arg=--allow-undefined" cargo +nightly build \
+//!     --release --lib --target wasm32-unknown-unknown -Zbuild-std=std,panic_abort --target-dir target/mvp
+//!   cd gas-bench && cargo run --release -- ../target/mvp/wasm32-unknown-unknown/release/reclaim_xion.wasm report.md
+
+use std::fmt::Write;
+
+use cosmwasm_std::testing::{message_info, mock_env};
+use cosmwasm_std::{Addr, Response, Uint128};
+use cosmwasm_vm::testing::{
+    execute, instantiate, mock_instance_with_options, MockApi, MockInstanceOptions, MockQuerier,
+    MockStorage,
+};
+use cosmwasm_vm::Instance;
+
+use reclaim_xion::claims::{ClaimInfo, MAX_CLAIM_TEXT_BYTES};
+use reclaim_xion::msg::{ExecuteMsg, InstantiateMsg, ProofMsg};
+use reclaim_xion::state::{EpochPolicy, SizeLimits};
+use reclaim_xion::testing::{
+    selected_witnesses, sign_proof, test_claim, test_epoch, test_witnesses, TestWitness,
+    TEST_PROVIDER_HASH,
+};
+
+type VmInstance = Instance<MockApi, MockStorage, MockQuerier>;
+
+const WITNESS_COUNTS: [u32; 6] = [1, 2, 4, 8, 16, 32];
+const PAYLOAD_SIZES: [usize; 5] = [0, 1024, 4 * 1024, 16 * 1024, 64 * 1024];
+// The parameters of the witness count runs, about the size of a real claim
+const DEFAULT_PAYLOAD: usize = 1024;
+// wasmd's DefaultGasMultiplier, CosmWasm gas per Cosmos SDK gas
+const GAS_MULTIPLIER: u64 = 140_000_000;
+const GAS_LIMIT: u64 = u64::MAX / 2;
+
+struct Bench {
+    instance: VmInstance,
+    owner: Addr,
+}
+
+impl Bench {
+    fn new(wasm: &[u8]) -> (Self, u64) {
+        let options = MockInstanceOptions {
+            gas_limit: GAS_LIMIT,
+            ..Default::default()
+        };
+        let instance = mock_instance_with_options(wasm, options);
+        let owner = Addr::unchecked(MockApi::default().addr_make("owner"));
+        let mut bench = Bench { instance, owner };
+
+        let msg = InstantiateMsg {
+            owner: bench.owner.to_string(),
+            badge: None,
+            guardian: None,
+        };
+        let info = message_info(&bench.owner, &[]);
+        let gas = bench.measure(|instance| instantiate(instance, mock_env(), info, msg).unwrap());
+        // lift the size limits so the largest payloads are measured rather than rejected
+        bench.execute(ExecuteMsg::UpdateLimits {
+            limits: SizeLimits {
+                max_parameters_bytes: MAX_CLAIM_TEXT_BYTES as u32,
+                max_context_bytes: MAX_CLAIM_TEXT_BYTES as u32,
+                max_signatures: u32::MAX,
+                max_witnesses: u32::MAX,
+            },
+        });
+        (bench, gas)
+    }
+
+    // CosmWasm gas of `call`, metered inside the VM or by the backend
+    fn measure(&mut self, call: impl FnOnce(&mut VmInstance) -> Response) -> u64 {
+        let before = self.instance.create_gas_report();
+        call(&mut self.instance);
+        let after = self.instance.create_gas_report();
+        (before.remaining - after.remaining) + (after.used_externally - before.used_externally)
+    }
+
+    fn execute(&mut self, msg: ExecuteMsg) -> u64 {
+        let info = message_info(&self.owner, &[]);
+        self.measure(|instance| {
+            let res: cosmwasm_std::ContractResult<Response> =
+                execute(instance, mock_env(), info, msg);
+            res.unwrap()
+        })
+    }
+
+    fn add_epoch(&mut self, witnesses: &[TestWitness], minimum_witness: u32) -> u64 {
+        self.execute(ExecuteMsg::AddEpoch {
+            witness: witnesses.iter().map(TestWitness::witness).collect(),
+            minimum_witness: Uint128::from(minimum_witness),
+            policy: EpochPolicy::default(),
+            domain: None,
+        })
+    }
+}
+
+// Claim parameters of `size` bytes
+fn claim_info(size: usize) -> ClaimInfo {
+    let prefix = r#"{"body":""#;
+    let padding = size.saturating_sub(prefix.len() + 2);
+    ClaimInfo {
+        provider: "http".to_string(),
+        parameters: format!(r#"{}{}"}}"#, prefix, "a".repeat(padding)),
+        context: format!(r#"{{"providerHash":"{}"}}"#, TEST_PROVIDER_HASH),
+    }
+}
+
+fn sdk_gas(gas: u64) -> String {
+    format!("{:.2}", gas as f64 / GAS_MULTIPLIER as f64)
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let wasm_path = args
+        .next()
+        .unwrap_or("../target/mvp/wasm32-unknown-unknown/release/reclaim_xion.wasm".to_string());
+    let report_path = args.next().unwrap_or("report.md".to_string());
+    let wasm = std::fs::read(&wasm_path).expect("cannot read the contract wasm");
+
+    let mut report = String::new();
+    writeln!(report, "# Gas report\n").unwrap();
+    writeln!(
+        report,
+        "CosmWasm gas measured in cosmwasm-vm, for `{}` ({} bytes). SDK gas is the CosmWasm gas \
+         divided by wasmd's default multiplier of {}, and excludes the storage, message size \
+         and instance costs the chain adds.\n\n\
+         The contract's size limits are lifted for the run. With the default limits, rows over \
+         16 KiB of parameters, 32 signatures or 64 witnesses are rejected instead.\n",
+        wasm_path.rsplit('/').next().unwrap_or(&wasm_path),
+        wasm.len(),
+        GAS_MULTIPLIER
+    )
+    .unwrap();
+
+    let (_, gas) = Bench::new(&wasm);
+    writeln!(report, "## instantiate\n").unwrap();
+    writeln!(report, "| CosmWasm gas | SDK gas |\n|---:|---:|").unwrap();
+    writeln!(report, "| {} | {} |\n", gas, sdk_gas(gas)).unwrap();
+
+    writeln!(report, "## add_epoch\n").unwrap();
+    writeln!(
+        report,
+        "| witnesses | CosmWasm gas | SDK gas |\n|---:|---:|---:|"
+    )
+    .unwrap();
+    for count in WITNESS_COUNTS {
+        let (mut bench, _) = Bench::new(&wasm);
+        let gas = bench.add_epoch(&test_witnesses(count), count);
+        writeln!(report, "| {} | {} | {} |", count, gas, sdk_gas(gas)).unwrap();
+    }
+
+    writeln!(
+        report,
+        "\n## verify_proof by witness count\n\nThe epoch requires every witness and the claim has {} \
+         byte parameters. Selection draws witnesses with repetition, the proof is signed once by \
+         each distinct witness selected.\n",
+        DEFAULT_PAYLOAD
+    )
+    .unwrap();
+    writeln!(
+        report,
+        "| witnesses | signatures | CosmWasm gas | SDK gas |\n|---:|---:|---:|---:|"
+    )
+    .unwrap();
+    for count in WITNESS_COUNTS {
+        let (mut bench, _) = Bench::new(&wasm);
+        let witnesses = test_witnesses(count);
+        bench.add_epoch(&witnesses, count);
+
+        let epoch = test_epoch(1, &witnesses, count.into());
+        let time = mock_env().block.time;
+        let info = claim_info(DEFAULT_PAYLOAD);
+        let claim = test_claim(&info, 1, time.seconds());
+        let signers = selected_witnesses(&epoch, &witnesses, &claim.identifier, time);
+        let proof = sign_proof(info, claim, &signers);
+        let signatures = proof.signedClaim.signatures.len();
+
+        let gas = bench.execute(ExecuteMsg::VerifyProof(ProofMsg {
+            proof,
+            domain: None,
+            owner_signature: None,
+        }));
+        writeln!(
+            report,
+            "| {} | {} | {} | {} |",
+            count,
+            signatures,
+            gas,
+            sdk_gas(gas)
+        )
+        .unwrap();
+    }
+
+    writeln!(
+        report,
+        "\n## verify_proof by parameter size\n\nOne witness signs the claim.\n"
+    )
+    .unwrap();
+    writeln!(
+        report,
+        "| parameters (bytes) | CosmWasm gas | SDK gas |\n|---:|---:|---:|"
+    )
+    .unwrap();
+    for size in PAYLOAD_SIZES {
+        let (mut bench, _) = Bench::new(&wasm);
+        let witnesses = test_witnesses(1);
+        bench.add_epoch(&witnesses, 1);
+
+        let info = claim_info(size);
+        let claim = test_claim(&info, 1, mock_env().block.time.seconds());
+        let proof = sign_proof(info, claim, &[&witnesses[0]]);
+        let gas = bench.execute(ExecuteMsg::VerifyProof(ProofMsg {
+            proof,
+            domain: None,
+            owner_signature: None,
+        }));
+        writeln!(report, "| {} | {} | {} |", size, gas, sdk_gas(gas)).unwrap();
+    }
+
+    std::fs::write(&report_path, &report).expect("cannot write the report");
+    print!("{report}");
+}
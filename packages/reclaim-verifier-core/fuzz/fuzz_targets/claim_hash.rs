@@ -0,0 +1,24 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use reclaim_verifier_core::{ClaimInfo, CompleteClaimData};
+
+fuzz_target!(|input: (String, String, String, u64, u64)| {
+    let (provider, parameters, context, epoch, timestamp) = input;
+    let info = ClaimInfo {
+        provider,
+        parameters,
+        context,
+    };
+    let hash = info.hash();
+    assert_eq!(hash.len(), 66);
+    assert_eq!(hash, info.hash());
+
+    let claim = CompleteClaimData {
+        identifier: hash,
+        owner: info.provider,
+        epoch,
+        timestampS: timestamp,
+    };
+    assert_eq!(claim.serialise(), claim.serialise());
+});
@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use reclaim_verifier_core::{canonicalize_json, ClaimInfo};
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(context) = std::str::from_utf8(data) else {
+        return;
+    };
+    let info = ClaimInfo {
+        provider: String::new(),
+        parameters: String::new(),
+        context: context.to_string(),
+    };
+    let _ = info.parse_context();
+
+    // canonical text is a fixed point
+    if let Ok(canonical) = canonicalize_json(context) {
+        assert_eq!(canonicalize_json(&canonical).as_ref(), Ok(&canonical));
+    }
+});
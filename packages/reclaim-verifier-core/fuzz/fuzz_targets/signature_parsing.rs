@@ -0,0 +1,39 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use reclaim_verifier_core::claims::recover_eth_address;
+use reclaim_verifier_core::{
+    CompleteClaimData, NativeCrypto, SignatureScheme, SignedClaim, Witness,
+};
+
+// Malformed signatures have to come back as errors, on both recovery paths
+fuzz_target!(|input: (String, Vec<String>, Option<String>)| {
+    let (message, signatures, public_key) = input;
+    for signature in &signatures {
+        let _ = recover_eth_address(&message, signature.clone());
+    }
+
+    let claim = SignedClaim {
+        claim: CompleteClaimData {
+            identifier: message,
+            owner: "0x0000000000000000000000000000000000000000".to_string(),
+            epoch: 1,
+            timestampS: 1,
+        },
+        signatures,
+    };
+    let witnesses: Vec<Witness> = [
+        SignatureScheme::EthPersonalSign,
+        SignatureScheme::Secp256k1,
+        SignatureScheme::Ed25519,
+    ]
+    .into_iter()
+    .map(|scheme| Witness {
+        address: format!("{scheme:?}"),
+        host: String::new(),
+        scheme,
+        public_key: public_key.clone(),
+    })
+    .collect();
+    let _ = claim.resolve_signers(&NativeCrypto, &witnesses);
+});
@@ -0,0 +1,27 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use reclaim_verifier_core::{fetch_witness_for_claim, Witness};
+
+// The minimum is a u8 only to keep runs fast, selection accepts any u128 and draws that
+// many witnesses, more than the epoch has included
+fuzz_target!(|input: (u8, u8, u128, String, u64)| {
+    let (count, minimum, epoch, identifier, timestamp) = input;
+    let witnesses: Vec<Witness> = (0..count)
+        .map(|i| Witness {
+            address: format!("0x{i:040x}"),
+            host: String::new(),
+            scheme: Default::default(),
+            public_key: None,
+        })
+        .collect();
+
+    let selected =
+        fetch_witness_for_claim(&witnesses, minimum.into(), epoch, &identifier, timestamp);
+    if witnesses.is_empty() {
+        assert!(selected.is_empty());
+    } else {
+        assert_eq!(selected.len(), usize::from(minimum));
+    }
+    assert!(selected.iter().all(|witness| witnesses.contains(witness)));
+});
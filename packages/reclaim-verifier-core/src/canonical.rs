@@ -0,0 +1,396 @@
+//! Canonical JSON, as Reclaim's `canonicalize` writes claim parameters and context: object
+//! keys sorted by UTF-16 code units, no whitespace and `JSON.stringify` string escapes.
+//!
+//! Only integers in the range JavaScript represents exactly are supported, floats have no
+//! canonical form the contract can reproduce and are rejected.
+
+use std::fmt;
+
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{Schema, SchemaObject};
+use schemars::JsonSchema;
+use serde::de::{self, MapAccess, SeqAccess, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::VerifierError;
+
+// Number.MAX_SAFE_INTEGER
+const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Bytes a claim `parameters` or `context` can take in its canonical form. Longer ones are
+/// rejected while the proof is parsed, before a structured value is canonicalized.
+pub const MAX_CLAIM_TEXT_BYTES: usize = 256 * 1024;
+
+/// A JSON document, keeping object keys in the order they were written
+#[derive(Clone, Debug, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<(String, JsonValue)>),
+}
+
+impl JsonValue {
+    /// The canonical text of the value
+    pub fn canonicalize(&self) -> String {
+        let mut out = String::new();
+        write_canonical(self, &mut out);
+        out
+    }
+
+    /// Bytes of the canonical text, counted without writing it
+    pub fn canonical_len(&self) -> usize {
+        match self {
+            JsonValue::Null => 4,
+            JsonValue::Bool(value) => {
+                if *value {
+                    4
+                } else {
+                    5
+                }
+            }
+            JsonValue::Integer(value) => value.to_string().len(),
+            JsonValue::String(value) => string_len(value),
+            // brackets and the commas between items
+            JsonValue::Array(items) => {
+                1 + items.len().max(1) + items.iter().map(JsonValue::canonical_len).sum::<usize>()
+            }
+            // braces, the commas between entries and a colon per entry
+            JsonValue::Object(entries) => {
+                1 + entries.len().max(1)
+                    + entries
+                        .iter()
+                        .map(|(key, value)| string_len(key) + 1 + value.canonical_len())
+                        .sum::<usize>()
+            }
+        }
+    }
+}
+
+/// Rewrites a JSON document in canonical form
+pub fn canonicalize_json(json: &str) -> Result<String, VerifierError> {
+    let value: JsonValue = serde_json_wasm::from_str(json)
+        .map_err(|err| VerifierError::InvalidJson(err.to_string()))?;
+    Ok(value.canonicalize())
+}
+
+/// Serde adapter for the claim fields that are hashed as text: a JSON string is taken as is,
+/// any other JSON value is canonicalized. Either is rejected over `MAX_CLAIM_TEXT_BYTES`.
+pub fn deserialize_json_text<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<String, D::Error> {
+    let value = JsonValue::deserialize(deserializer)?;
+    let size = match &value {
+        JsonValue::String(text) => text.len(),
+        value => value.canonical_len(),
+    };
+    if size > MAX_CLAIM_TEXT_BYTES {
+        return Err(de::Error::custom(format!(
+            "claim text is {size} bytes, the limit is {MAX_CLAIM_TEXT_BYTES}"
+        )));
+    }
+    match value {
+        JsonValue::String(text) => Ok(text),
+        value => Ok(value.canonicalize()),
+    }
+}
+
+fn write_canonical(value: &JsonValue, out: &mut String) {
+    match value {
+        JsonValue::Null => out.push_str("null"),
+        JsonValue::Bool(value) => out.push_str(if *value { "true" } else { "false" }),
+        JsonValue::Integer(value) => out.push_str(&value.to_string()),
+        JsonValue::String(value) => write_string(value, out),
+        JsonValue::Array(items) => {
+            out.push('[');
+            for (index, item) in items.iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        JsonValue::Object(entries) => {
+            // JavaScript compares strings by UTF-16 code units, not by code points
+            let mut entries: Vec<_> = entries.iter().collect();
+            entries.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+            out.push('{');
+            for (index, (key, value)) in entries.into_iter().enumerate() {
+                if index > 0 {
+                    out.push(',');
+                }
+                write_string(key, out);
+                out.push(':');
+                write_canonical(value, out);
+            }
+            out.push('}');
+        }
+    }
+}
+
+// Bytes of the string as `write_string` writes it, quotes included
+fn string_len(value: &str) -> usize {
+    let escaped: usize = value
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' | '\u{08}' | '\u{0c}' | '\n' | '\r' | '\t' => 2,
+            c if c < ' ' => 6,
+            c => c.len_utf8(),
+        })
+        .sum();
+    escaped + 2
+}
+
+// Escapes like JSON.stringify, everything above the control characters is written as is
+fn write_string(value: &str, out: &mut String) {
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{08}' => out.push_str("\\b"),
+            '\u{0c}' => out.push_str("\\f"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+impl Serialize for JsonValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            JsonValue::Null => serializer.serialize_unit(),
+            JsonValue::Bool(value) => serializer.serialize_bool(*value),
+            JsonValue::Integer(value) => serializer.serialize_i64(*value),
+            JsonValue::String(value) => serializer.serialize_str(value),
+            JsonValue::Array(items) => {
+                let mut seq = serializer.serialize_seq(Some(items.len()))?;
+                for item in items {
+                    seq.serialize_element(item)?;
+                }
+                seq.end()
+            }
+            JsonValue::Object(entries) => {
+                let mut map = serializer.serialize_map(Some(entries.len()))?;
+                for (key, value) in entries {
+                    map.serialize_entry(key, value)?;
+                }
+                map.end()
+            }
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for JsonValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(JsonValueVisitor)
+    }
+}
+
+struct JsonValueVisitor;
+
+impl<'de> Visitor<'de> for JsonValueVisitor {
+    type Value = JsonValue;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON value without floats")
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<JsonValue, E> {
+        Ok(JsonValue::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<JsonValue, D::Error> {
+        JsonValue::deserialize(deserializer)
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<JsonValue, E> {
+        Ok(JsonValue::Bool(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<JsonValue, E> {
+        if value.unsigned_abs() > MAX_SAFE_INTEGER {
+            return Err(E::custom(format!(
+                "integer {value} is not a safe JSON integer"
+            )));
+        }
+        Ok(JsonValue::Integer(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<JsonValue, E> {
+        if value > MAX_SAFE_INTEGER {
+            return Err(E::custom(format!(
+                "integer {value} is not a safe JSON integer"
+            )));
+        }
+        Ok(JsonValue::Integer(value as i64))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<JsonValue, E> {
+        Err(E::custom(format!("float {value} has no canonical form")))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(value.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<JsonValue, E> {
+        Ok(JsonValue::String(value))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<JsonValue, A::Error> {
+        let mut items = vec![];
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(JsonValue::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<JsonValue, A::Error> {
+        let mut entries: Vec<(String, JsonValue)> = vec![];
+        while let Some((key, value)) = map.next_entry::<String, JsonValue>()? {
+            // the canonical form of an object with a repeated key is ambiguous
+            if entries.iter().any(|(existing, _)| *existing == key) {
+                return Err(de::Error::custom(format!("duplicate key {key}")));
+            }
+            entries.push((key, value));
+        }
+        Ok(JsonValue::Object(entries))
+    }
+}
+
+impl JsonSchema for JsonValue {
+    fn schema_name() -> String {
+        "JsonValue".to_string()
+    }
+
+    // any JSON value, a string is taken as already serialised JSON
+    fn json_schema(_gen: &mut SchemaGenerator) -> Schema {
+        SchemaObject::default().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sorts_nested_objects_and_strips_whitespace() {
+        let json = r#"{
+            "z": { "y": [3, { "b": 1, "a": -2 }], "x": null },
+            "a": true,
+            "m": []
+        }"#;
+        assert_eq!(
+            canonicalize_json(json).unwrap(),
+            r#"{"a":true,"m":[],"z":{"x":null,"y":[3,{"a":-2,"b":1}]}}"#
+        );
+    }
+
+    #[test]
+    fn escapes_strings_like_json_stringify() {
+        let json = r#"{"b":"café 😀","a":"\u0001\u001f\b\f\n\r\t\"\\\/","c":"\u007f\u2028"}"#;
+        assert_eq!(
+            canonicalize_json(json).unwrap(),
+            "{\"a\":\"\\u0001\\u001f\\b\\f\\n\\r\\t\\\"\\\\/\",\"b\":\"café 😀\",\"c\":\"\u{7f}\u{2028}\"}"
+        );
+    }
+
+    #[test]
+    fn sorts_keys_by_utf16_code_units() {
+        // U+1F600 is 0xD83D 0xDE00 in UTF-16, so it sorts before U+E000 unlike in UTF-8
+        let json = "{\"\u{e000}\":1,\"\u{1f600}\":2,\"Z\":3,\"a\":4}";
+        assert_eq!(
+            canonicalize_json(json).unwrap(),
+            "{\"Z\":3,\"a\":4,\"\u{1f600}\":2,\"\u{e000}\":1}"
+        );
+    }
+
+    #[test]
+    fn rejects_what_has_no_canonical_form() {
+        for json in [
+            r#"{"a":1.5}"#,
+            r#"{"a":1e3}"#,
+            r#"{"a":9007199254740992}"#,
+            r#"{"a":1,"a":2}"#,
+            r#"{"a":"#,
+        ] {
+            assert!(
+                matches!(canonicalize_json(json), Err(VerifierError::InvalidJson(_))),
+                "{}",
+                json
+            );
+        }
+        assert_eq!(
+            canonicalize_json(r#"[9007199254740991,-9007199254740991]"#).unwrap(),
+            "[9007199254740991,-9007199254740991]"
+        );
+    }
+
+    #[test]
+    fn canonical_len_counts_the_canonical_text() {
+        for json in [
+            r#"{"b":[{"d":"\n","c":0}],"a":"x\u0001é","e":{},"f":[],"g":[null,true,false,-12]}"#,
+            r#"[]"#,
+            r#""\"\\""#,
+        ] {
+            let value: JsonValue = serde_json_wasm::from_str(json).unwrap();
+            assert_eq!(
+                value.canonical_len(),
+                value.canonicalize().len(),
+                "{}",
+                json
+            );
+        }
+    }
+
+    #[test]
+    fn claim_text_over_the_limit_is_rejected_before_canonicalizing() {
+        #[derive(Deserialize)]
+        struct Text {
+            #[serde(deserialize_with = "deserialize_json_text")]
+            text: String,
+        }
+
+        let within = format!(
+            r#"{{"text":{{"a":"{}"}}}}"#,
+            "x".repeat(MAX_CLAIM_TEXT_BYTES - 8)
+        );
+        let text: Text = serde_json_wasm::from_str(&within).unwrap();
+        assert_eq!(text.text.len(), MAX_CLAIM_TEXT_BYTES);
+
+        let over = format!(
+            r#"{{"text":{{"a":"{}"}}}}"#,
+            "x".repeat(MAX_CLAIM_TEXT_BYTES - 7)
+        );
+        assert!(serde_json_wasm::from_str::<Text>(&over).is_err());
+        let over = format!(r#"{{"text":"{}"}}"#, "x".repeat(MAX_CLAIM_TEXT_BYTES + 1));
+        assert!(serde_json_wasm::from_str::<Text>(&over).is_err());
+    }
+
+    #[test]
+    fn canonical_text_is_stable() {
+        let json = r#"{"b":[{"d":"\n","c":0}],"a":"x"}"#;
+        let canonical = canonicalize_json(json).unwrap();
+        assert_eq!(canonicalize_json(&canonical).unwrap(), canonical);
+
+        let value: JsonValue = serde_json_wasm::from_str(json).unwrap();
+        let written = serde_json_wasm::to_string(&value).unwrap();
+        assert_eq!(
+            serde_json_wasm::from_str::<JsonValue>(&written).unwrap(),
+            value
+        );
+    }
+}
@@ -0,0 +1,163 @@
+#![allow(non_snake_case)]
+
+use std::collections::BTreeMap;
+
+use crate::canonical::{deserialize_json_text, JsonValue};
+use crate::crypto::{resolve_signer, CryptoBackend};
+use crate::witness::Witness;
+use crate::VerifierError;
+#[cfg(any(test, feature = "native"))]
+mod identity_digest;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+pub fn append_0x(content: &str) -> String {
+    let mut initializer = String::from("0x");
+    initializer.push_str(content);
+    initializer
+}
+
+pub fn keccak256(message: &str) -> Vec<u8> {
+    let message: &[u8] = message.as_ref();
+
+    let mut eth_message = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    eth_message.extend_from_slice(message);
+    let mut hasher = Keccak256::new();
+    hasher.update(&eth_message);
+
+    hasher.finalize().to_vec()
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ClaimInfo {
+    pub provider: String,
+    /// Hashed as given when a JSON string, other JSON values are canonicalized first
+    #[serde(deserialize_with = "deserialize_json_text")]
+    #[schemars(with = "JsonValue")]
+    pub parameters: String,
+    /// Hashed as given when a JSON string, other JSON values are canonicalized first
+    #[serde(deserialize_with = "deserialize_json_text")]
+    #[schemars(with = "JsonValue")]
+    pub context: String,
+}
+
+impl ClaimInfo {
+    pub fn hash(&self) -> String {
+        let mut hasher = Keccak256::new();
+        let hash_str = format!(
+            "{}\n{}\n{}",
+            &self.provider, &self.parameters, &self.context
+        );
+        hasher.update(&hash_str);
+
+        let hash = hasher.finalize().to_vec();
+        append_0x(hex::encode(hash).as_str())
+    }
+
+    pub fn parse_context(&self) -> Result<ClaimContext, VerifierError> {
+        serde_json_wasm::from_str(&self.context)
+            .map_err(|err| VerifierError::InvalidContext(err.to_string()))
+    }
+}
+
+/// The fields of `ClaimInfo::context` the contract reads, other keys are ignored
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ClaimContext {
+    #[serde(default)]
+    pub extractedParameters: BTreeMap<String, String>,
+    #[serde(default)]
+    pub providerHash: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CompleteClaimData {
+    pub identifier: String,
+    pub owner: String,
+    pub epoch: u64,
+    pub timestampS: u64,
+}
+
+impl CompleteClaimData {
+    pub fn serialise(&self) -> String {
+        format!(
+            "{}\n{}\n{}\n{}",
+            &self.identifier, &self.owner, &self.timestampS, &self.epoch
+        )
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SignedClaim {
+    pub claim: CompleteClaimData,
+    pub signatures: Vec<String>,
+}
+
+impl SignedClaim {
+    /// Resolves every signature to the address of the witness that made it, dispatching on
+    /// the signature scheme of the `witnesses` it may come from
+    pub fn resolve_signers(
+        &self,
+        crypto: &dyn CryptoBackend,
+        witnesses: &[Witness],
+    ) -> Result<Vec<String>, VerifierError> {
+        let message = self.claim.serialise();
+        let mut signers = vec![];
+        for (index, signature) in self.signatures.iter().enumerate() {
+            let signer = hex::decode(signature.trim_start_matches("0x"))
+                .map_err(|_| VerifierError::SignatureErr {})
+                .and_then(|signature| resolve_signer(crypto, &message, &signature, witnesses))
+                .map_err(|_| VerifierError::InvalidSignature { index })?;
+            signers.push(signer);
+        }
+        Ok(signers)
+    }
+}
+
+/// Recovers the address of the Ethereum key that personal-signed `message`
+#[cfg(any(test, feature = "native"))]
+pub fn recover_eth_address(
+    message: &str,
+    complete_signature: String,
+) -> Result<String, VerifierError> {
+    use crate::claims::identity_digest::Identity256;
+    use digest::Update;
+    use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+    let bm = keccak256(message);
+    let message_hash = bm.to_vec();
+
+    // r || s || v, where v is 27 or 28
+    let signature = hex::decode(complete_signature.trim_start_matches("0x"))
+        .map_err(|_| VerifierError::SignatureErr {})?;
+    let [r_s @ .., rec_param] = signature.as_slice() else {
+        return Err(VerifierError::SignatureErr {});
+    };
+
+    let id = match rec_param {
+        27 => RecoveryId::new(false, false),
+        28 => RecoveryId::new(true, false),
+        _ => return Err(VerifierError::SignatureErr {}),
+    };
+
+    let signature = Signature::from_slice(r_s).map_err(|_| VerifierError::SignatureErr {})?;
+    let message_digest = Identity256::new().chain(&message_hash);
+
+    // Recover the public key
+    let verkey = VerifyingKey::recover_from_digest(message_digest, &signature, id)
+        .map_err(|_| VerifierError::SignatureErr {})?;
+    let key: Vec<u8> = verkey.to_encoded_point(false).as_bytes().into();
+    let hasher = Keccak256::new_with_prefix(&key[1..]);
+
+    let hash = hasher.finalize().to_vec();
+    Ok(append_0x(&hex::encode(&hash[12..])))
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Proof {
+    pub claimInfo: ClaimInfo,
+    pub signedClaim: SignedClaim,
+}
@@ -0,0 +1,119 @@
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+use crate::claims::{append_0x, keccak256};
+use crate::witness::{SignatureScheme, Witness};
+use crate::VerifierError;
+
+/// The signature primitives verification needs. On chain they are the host functions of
+/// the chain, off chain `NativeCrypto` implements them in Rust.
+pub trait CryptoBackend {
+    /// Uncompressed public key that made the 64 byte `signature` of `message_hash`
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Option<Vec<u8>>;
+
+    fn secp256k1_verify(&self, message_hash: &[u8], signature: &[u8], public_key: &[u8]) -> bool;
+
+    fn ed25519_verify(&self, message: &[u8], signature: &[u8], public_key: &[u8]) -> bool;
+}
+
+/// Pure Rust backend for services, indexers and tests running off chain
+#[cfg(any(test, feature = "native"))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NativeCrypto;
+
+#[cfg(any(test, feature = "native"))]
+impl CryptoBackend for NativeCrypto {
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Option<Vec<u8>> {
+        use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+        let signature = Signature::from_slice(signature).ok()?;
+        let id = RecoveryId::from_byte(recovery_param)?;
+        let key = VerifyingKey::recover_from_prehash(message_hash, &signature, id).ok()?;
+        Some(key.to_encoded_point(false).as_bytes().to_vec())
+    }
+
+    fn secp256k1_verify(&self, message_hash: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+        use k256::ecdsa::signature::hazmat::PrehashVerifier;
+        use k256::ecdsa::{Signature, VerifyingKey};
+
+        let (Ok(signature), Ok(key)) = (
+            Signature::from_slice(signature),
+            VerifyingKey::from_sec1_bytes(public_key),
+        ) else {
+            return false;
+        };
+        key.verify_prehash(message_hash, &signature).is_ok()
+    }
+
+    fn ed25519_verify(&self, message: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+        let (Ok(signature), Ok(key)) = (
+            ed25519_zebra::Signature::try_from(signature),
+            ed25519_zebra::VerificationKey::try_from(public_key),
+        ) else {
+            return false;
+        };
+        key.verify(&signature, message).is_ok()
+    }
+}
+
+/// The address of the witness among `witnesses` that signed `message`, recovering it for
+/// Ethereum personal signatures
+pub fn resolve_signer(
+    crypto: &dyn CryptoBackend,
+    message: &str,
+    signature: &[u8],
+    witnesses: &[Witness],
+) -> Result<String, VerifierError> {
+    // Keyed schemes can only be checked against the witnesses that may have signed
+    for witness in witnesses {
+        let Some(key) = witness.public_key_bytes() else {
+            continue;
+        };
+        let verified = match witness.scheme {
+            SignatureScheme::EthPersonalSign => continue,
+            SignatureScheme::Secp256k1 => {
+                let hash = Sha256::digest(message.as_bytes());
+                crypto.secp256k1_verify(&hash, signature, &key)
+            }
+            SignatureScheme::Ed25519 => crypto.ed25519_verify(message.as_bytes(), signature, &key),
+        };
+        if verified {
+            return Ok(witness.address.clone());
+        }
+    }
+
+    // Anything else has to be an Ethereum personal signature, which names its signer
+    recover_eth_signer(crypto, message, signature)
+}
+
+/// Recovers the address of the Ethereum key that personal-signed `message`
+pub fn recover_eth_signer(
+    crypto: &dyn CryptoBackend,
+    message: &str,
+    signature: &[u8],
+) -> Result<String, VerifierError> {
+    if signature.len() != 65 {
+        return Err(VerifierError::SignatureErr {});
+    }
+    let recovery_param = match signature[64] {
+        27 => 0,
+        28 => 1,
+        _ => return Err(VerifierError::SignatureErr {}),
+    };
+
+    let key = crypto
+        .secp256k1_recover_pubkey(&keccak256(message), &signature[..64], recovery_param)
+        .ok_or(VerifierError::SignatureErr {})?;
+    let hash = Keccak256::new_with_prefix(&key[1..]).finalize();
+    Ok(append_0x(&hex::encode(&hash[12..])))
+}
@@ -0,0 +1,13 @@
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum VerifierError {
+    #[error("Signature not appropriate")]
+    SignatureErr {},
+    #[error("Signature {index} is malformed or does not verify")]
+    InvalidSignature { index: usize },
+    #[error("Invalid claim context: {0}")]
+    InvalidContext(String),
+    #[error("Invalid JSON: {0}")]
+    InvalidJson(String),
+}
@@ -0,0 +1,147 @@
+//! Verification logic of the Reclaim verifier contract with no chain dependency, so claims
+//! can be hashed, assigned to witnesses and checked the same way on and off chain.
+
+pub mod canonical;
+pub mod claims;
+pub mod crypto;
+mod error;
+pub mod witness;
+
+pub use crate::canonical::{canonicalize_json, JsonValue, MAX_CLAIM_TEXT_BYTES};
+pub use crate::claims::{ClaimContext, ClaimInfo, CompleteClaimData, Proof, SignedClaim};
+pub use crate::crypto::CryptoBackend;
+#[cfg(any(test, feature = "native"))]
+pub use crate::crypto::NativeCrypto;
+pub use crate::error::VerifierError;
+pub use crate::witness::{fetch_witness_for_claim, SignatureScheme, Witness};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claims::recover_eth_address;
+    use proptest::prelude::*;
+
+    const RECLAIM_ADDRESS: &str = "0x244897572368eadf65bfbc5aec98d8e5443a9072";
+
+    fn signed_claim() -> SignedClaim {
+        SignedClaim {
+            claim: CompleteClaimData {
+                identifier: "0x5fba1c86439db035389d90f8025739c54849db4cfb7cf91aa3fb02abd9c1f83a"
+                    .to_string(),
+                owner: "0x612c00c6d44fa281beeea91805349519ef3c3e83".to_string(),
+                epoch: 1_u64,
+                timestampS: 1748539856,
+            },
+            signatures: vec![
+                "0x04fac06fb875a8a4896912461655f039b9b7726b1eacc1727f4b87c04b3971951387dc60b884e80e5c866722c1e34738a41c163f6c6bca2e33759a5ed34538201b".to_string()
+            ],
+        }
+    }
+
+    fn witness(address: &str) -> Witness {
+        Witness {
+            address: address.to_string(),
+            host: "https://valid-witness.com".to_string(),
+            scheme: SignatureScheme::EthPersonalSign,
+            public_key: None,
+        }
+    }
+
+    #[test]
+    fn native_crypto_recovers_the_witness() {
+        let claim = signed_claim();
+        let signers = claim
+            .resolve_signers(&NativeCrypto, &[witness(RECLAIM_ADDRESS)])
+            .unwrap();
+        assert_eq!(signers, vec![RECLAIM_ADDRESS.to_string()]);
+
+        let recovered =
+            recover_eth_address(&claim.claim.serialise(), claim.signatures[0].clone()).unwrap();
+        assert_eq!(recovered, RECLAIM_ADDRESS);
+    }
+
+    #[test]
+    fn malformed_signature_is_reported_by_index() {
+        let mut claim = signed_claim();
+        claim.signatures.push("0x1234".to_string());
+        let err = claim.resolve_signers(&NativeCrypto, &[]).unwrap_err();
+        assert_eq!(err, VerifierError::InvalidSignature { index: 1 });
+    }
+
+    #[test]
+    fn witness_selection_is_deterministic() {
+        let witnesses: Vec<Witness> = (0..5).map(|i| witness(&format!("0x{i:040x}"))).collect();
+        let identifier = signed_claim().claim.identifier;
+        let first = fetch_witness_for_claim(&witnesses, 3, 1, &identifier, 1_000);
+        let second = fetch_witness_for_claim(&witnesses, 3, 1, &identifier, 1_000);
+        assert_eq!(first.len(), 3);
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn invalid_context_is_reported() {
+        let info = ClaimInfo {
+            provider: "http".to_string(),
+            parameters: "{}".to_string(),
+            context: "not json".to_string(),
+        };
+        assert!(matches!(
+            info.parse_context(),
+            Err(VerifierError::InvalidContext(_))
+        ));
+    }
+
+    #[test]
+    fn no_witnesses_selects_nobody() {
+        assert_eq!(fetch_witness_for_claim(&[], 5, 1, "0x01", 1), vec![]);
+    }
+
+    proptest! {
+        #[test]
+        fn signature_parsing_never_panics(
+            message in any::<String>(),
+            signature in prop_oneof!["(0x)?[0-9a-f]{0,140}", any::<String>()],
+        ) {
+            let _ = recover_eth_address(&message, signature.clone());
+            let claim = SignedClaim {
+                signatures: vec![signature],
+                ..signed_claim()
+            };
+            let _ = claim.resolve_signers(&NativeCrypto, &[witness(RECLAIM_ADDRESS)]);
+        }
+
+        #[test]
+        fn claim_hash_is_deterministic(
+            provider in any::<String>(),
+            parameters in any::<String>(),
+            context in any::<String>(),
+        ) {
+            let info = ClaimInfo { provider, parameters, context };
+            let hash = info.hash();
+            prop_assert_eq!(hash.len(), 66);
+            prop_assert_eq!(hash, info.clone().hash());
+            let _ = info.parse_context();
+            let _ = canonicalize_json(&info.context);
+        }
+
+        #[test]
+        fn witness_selection_is_bounded_and_deterministic(
+            count in 0usize..8,
+            minimum in 0u128..40,
+            epoch in any::<u128>(),
+            identifier in any::<String>(),
+            timestamp in any::<u64>(),
+        ) {
+            let witnesses: Vec<Witness> =
+                (0..count).map(|i| witness(&format!("0x{i:040x}"))).collect();
+            let selected = fetch_witness_for_claim(&witnesses, minimum, epoch, &identifier, timestamp);
+            let expected = if count == 0 { 0 } else { minimum as usize };
+            prop_assert_eq!(selected.len(), expected);
+            prop_assert!(selected.iter().all(|w| witnesses.contains(w)));
+            prop_assert_eq!(
+                selected,
+                fetch_witness_for_claim(&witnesses, minimum, epoch, &identifier, timestamp)
+            );
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Witness {
+    /// Stored lowercase. Keyed schemes have to use the address derived from their public key
+    pub address: String,
+    pub host: String,
+    #[serde(default)]
+    pub scheme: SignatureScheme,
+    /// Hex encoded public key, required by the schemes that cannot recover their signer
+    #[serde(default)]
+    pub public_key: Option<String>,
+}
+
+/// How a witness signs claims
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureScheme {
+    /// secp256k1 over the keccak256 of the `\x19Ethereum Signed Message` prefixed claim,
+    /// the signer is recovered from the 65 byte signature and matched on `address`
+    #[default]
+    EthPersonalSign,
+    /// secp256k1 over the sha256 of the claim, checked against `public_key`
+    Secp256k1,
+    /// ed25519 over the claim, checked against `public_key`
+    Ed25519,
+}
+
+impl Witness {
+    pub fn public_key_bytes(&self) -> Option<Vec<u8>> {
+        let key = self.public_key.as_ref()?;
+        hex::decode(key.trim_start_matches("0x")).ok()
+    }
+
+    // A well formed key for the scheme, eth witnesses are identified by address alone
+    pub fn has_valid_key(&self) -> bool {
+        match self.scheme {
+            SignatureScheme::EthPersonalSign => true,
+            SignatureScheme::Secp256k1 => {
+                matches!(self.public_key_bytes(), Some(key) if key.len() == 33 || key.len() == 65)
+            }
+            SignatureScheme::Ed25519 => {
+                matches!(self.public_key_bytes(), Some(key) if key.len() == 32)
+            }
+        }
+    }
+
+    /// The address a keyed witness has to go by, `0x` and the first 20 bytes of the sha256 of
+    /// its public key, so no key can take the address of another witness. Eth witnesses are
+    /// named by the key their signatures recover.
+    pub fn key_address(&self) -> Option<String> {
+        if self.scheme == SignatureScheme::EthPersonalSign {
+            return None;
+        }
+        let key = self.public_key_bytes()?;
+        Some(format!("0x{}", hex::encode(&Sha256::digest(key)[..20])))
+    }
+
+    pub fn get_addresses(witness: Vec<Witness>) -> Vec<String> {
+        let mut vec_addresses = vec![];
+        for wit in witness {
+            vec_addresses.push(wit.address);
+        }
+        vec_addresses
+    }
+}
+
+fn generate_random_seed(bytes: &[u8], offset: usize) -> u32 {
+    // Convert the hash result into a u32 using the offset, wrapping around the end
+    let mut seed = 0u32;
+    for i in 0..4 {
+        seed |= u32::from(bytes[(offset + i) % bytes.len()]) << (i * 8);
+    }
+
+    seed
+}
+
+/// Selects the witnesses that have to sign a claim, `timestamp` is in nanoseconds
+pub fn fetch_witness_for_claim(
+    witnesses: &[Witness],
+    minimum_witness: u128,
+    epoch_id: u128,
+    identifier: &str,
+    timestamp: u64,
+) -> Vec<Witness> {
+    let mut selected_witness = vec![];
+    if witnesses.is_empty() {
+        return selected_witness;
+    }
+
+    // Create a hash from identifier+epoch+minimum+timestamp
+    let hash_str = format!(
+        "{}\n{}\n{}\n{}",
+        hex::encode(identifier),
+        minimum_witness,
+        timestamp,
+        epoch_id
+    );
+    let result = hash_str.as_bytes().to_vec();
+    let mut hasher = Sha256::new();
+    hasher.update(result);
+    let hash_result = hasher.finalize().to_vec();
+    let mut byte_offset = 0;
+    let witness_left = witnesses.len();
+    for _i in 0..minimum_witness {
+        let random_seed = generate_random_seed(&hash_result, byte_offset) as usize;
+        let witness_index = random_seed % witness_left;
+        let witness = witnesses.get(witness_index);
+        if let Some(data) = witness {
+            selected_witness.push(data.clone());
+        }
+        byte_offset = (byte_offset + 4) % hash_result.len();
+    }
+
+    selected_witness
+}
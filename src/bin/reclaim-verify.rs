@@ -0,0 +1,147 @@
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use cosmwasm_std::{from_json, Timestamp};
+use reclaim_verifier_core::NativeCrypto;
+
+use reclaim_xion::claims::{Proof, ProofFormat};
+use reclaim_xion::contract::{check_signers, fetch_witness_for_claim};
+use reclaim_xion::msg::GetEpochResponse;
+use reclaim_xion::state::Epoch;
+
+const USAGE: &str = "usage: reclaim-verify <proof.json> <epoch.json> [--time <unix seconds> | --time-nanos <unix nanoseconds>]
+
+Checks a Reclaim proof offline, the way the contract would at the given block time.
+Witness selection uses the block time in nanoseconds, so --time-nanos reproduces a past
+verification exactly, --time only when the block was on a whole second.
+The proof file is a `Proof`, or the proof the Reclaim JS SDK emits.
+The epoch file is an `Epoch`, or the `GetEpoch` query response wrapping one.";
+
+// Verifies a proof without a chain: `cargo run --bin reclaim-verify -- proof.json epoch.json`
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{USAGE}");
+        return ExitCode::SUCCESS;
+    }
+    let (proof, epoch, time) = match parse_args(&args) {
+        Ok(inputs) => inputs,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return ExitCode::from(2);
+        }
+    };
+
+    let reasons = verify(&proof, epoch, time);
+    if reasons.is_empty() {
+        println!("verdict: PASS");
+        ExitCode::SUCCESS
+    } else {
+        println!("verdict: FAIL");
+        for reason in reasons {
+            println!("  - {reason}");
+        }
+        ExitCode::FAILURE
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<(Proof, Epoch, Timestamp), String> {
+    let mut files = vec![];
+    let mut time = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--time" => {
+                let seconds = args
+                    .next()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .ok_or("--time expects unix seconds")?;
+                time = Some(Timestamp::from_seconds(seconds));
+            }
+            "--time-nanos" => {
+                let nanos = args
+                    .next()
+                    .and_then(|value| value.parse::<u64>().ok())
+                    .ok_or("--time-nanos expects unix nanoseconds")?;
+                time = Some(Timestamp::from_nanos(nanos));
+            }
+            file => files.push(file),
+        }
+    }
+    let [proof_file, epoch_file] = files[..] else {
+        return Err("expected a proof file and an epoch file".to_string());
+    };
+
+    let proof = read(proof_file)?;
+    let proof = from_json::<ProofFormat>(&proof)
+        .map_err(|err| format!("{proof_file} is not a proof: {err}"))?;
+    let proof = Proof::try_from(proof).map_err(|err| err.to_string())?;
+    let epoch = read(epoch_file)?;
+    let epoch = from_json::<Epoch>(&epoch)
+        .or_else(|_| from_json::<GetEpochResponse>(&epoch).map(|res| res.epoch))
+        .map_err(|err| format!("{epoch_file} is not an epoch: {err}"))?;
+
+    let time = match time {
+        Some(time) => time,
+        None => {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_err(|err| err.to_string())?;
+            Timestamp::from_nanos(now.as_nanos() as u64)
+        }
+    };
+    Ok((proof, epoch, time))
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|err| format!("cannot read {path}: {err}"))
+}
+
+// Prints every step of the verification and returns the reasons the proof fails
+fn verify(proof: &Proof, epoch: Epoch, time: Timestamp) -> Vec<String> {
+    let mut reasons = vec![];
+    let claim = &proof.signedClaim.claim;
+
+    let computed = proof.claimInfo.hash();
+    println!("identifier: {}", claim.identifier);
+    println!("computed identifier: {computed}");
+    if claim.identifier != computed {
+        reasons.push("the claim identifier is not the hash of the claim info".to_string());
+    }
+
+    println!("epoch: {}", epoch.id);
+    if u128::from(claim.epoch) != epoch.id.u128() {
+        reasons.push(format!(
+            "the claim is for epoch {}, the epoch file is epoch {}",
+            claim.epoch, epoch.id
+        ));
+    }
+    if epoch.ended {
+        reasons.push(format!("epoch {} has ended", epoch.id));
+    }
+
+    let policy = epoch.policy.clone();
+    let expected_witness = fetch_witness_for_claim(epoch, claim.identifier.clone(), time);
+    println!("expected witnesses (at {} ns):", time.nanos());
+    for witness in &expected_witness {
+        println!("  {} {}", witness.address, witness.host);
+    }
+
+    match proof
+        .signedClaim
+        .resolve_signers(&NativeCrypto, &expected_witness)
+    {
+        Ok(signers) => {
+            println!("recovered signers:");
+            for signer in &signers {
+                println!("  {signer}");
+            }
+            if let Err(err) = check_signers(&signers, &expected_witness, &policy) {
+                reasons.push(err.to_string());
+            }
+        }
+        Err(err) => reasons.push(err.to_string()),
+    }
+
+    reasons
+}
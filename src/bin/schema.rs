@@ -0,0 +1,17 @@
+use cosmwasm_schema::write_api;
+
+use reclaim_xion::msg::{ExecuteMsg, InstantiateMsg, QueryMsg, SudoMsg};
+
+// Regenerate the committed schema with `cargo run --bin schema`
+fn main() {
+    write_api! {
+        instantiate: InstantiateMsg,
+        execute: ExecuteMsg,
+        query: QueryMsg,
+        sudo: SudoMsg,
+    }
+
+    // write_api clears the schema directory, so the error table is written after it
+    std::fs::write("schema/error_codes.json", reclaim_xion::error_codes_json()).unwrap();
+    println!("Exported schema/error_codes.json");
+}
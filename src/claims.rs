@@ -1,139 +1,181 @@
-#![allow(non_snake_case)]
-
-use crate::ContractError;
-mod identity_digest;
-use cosmwasm_std::DepsMut;
-use k256::{
-    ecdsa::{RecoveryId, Signature, VerifyingKey}, // type aliases
+pub use reclaim_verifier_core::claims::{
+    append_0x, keccak256, ClaimContext, ClaimInfo, CompleteClaimData, Proof, SignedClaim,
 };
+pub use reclaim_verifier_core::crypto::{recover_eth_signer, resolve_signer};
+pub use reclaim_verifier_core::MAX_CLAIM_TEXT_BYTES;
+
+use std::collections::BTreeMap;
+
+use cosmwasm_std::Api;
+use reclaim_verifier_core::canonical::deserialize_json_text;
+use reclaim_verifier_core::{CryptoBackend, JsonValue};
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
-use sha3::{Digest, Keccak256};
+use serde::{Deserialize, Deserializer, Serialize};
 
-pub fn append_0x(content: &str) -> String {
-    let mut initializer = String::from("0x");
-    initializer.push_str(content);
-    initializer
-}
+use crate::ContractError;
 
-pub fn keccak256(message: &str) -> Vec<u8> {
-    let message: &[u8] = message.as_ref();
+/// Verifies signatures through the crypto host functions of the chain
+pub struct ApiCrypto<'a>(pub &'a dyn Api);
+
+impl CryptoBackend for ApiCrypto<'_> {
+    fn secp256k1_recover_pubkey(
+        &self,
+        message_hash: &[u8],
+        signature: &[u8],
+        recovery_param: u8,
+    ) -> Option<Vec<u8>> {
+        self.0
+            .secp256k1_recover_pubkey(message_hash, signature, recovery_param)
+            .ok()
+    }
 
-    let mut eth_message = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
-    eth_message.extend_from_slice(message);
-    let mut hasher = Keccak256::new();
-    hasher.update(&eth_message);
+    fn secp256k1_verify(&self, message_hash: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+        self.0
+            .secp256k1_verify(message_hash, signature, public_key)
+            .unwrap_or(false)
+    }
 
-    let hash = hasher.finalize().to_vec();
-    hash
+    fn ed25519_verify(&self, message: &[u8], signature: &[u8], public_key: &[u8]) -> bool {
+        self.0
+            .ed25519_verify(message, signature, public_key)
+            .unwrap_or(false)
+    }
 }
 
+/// A proof as the Reclaim JS SDK emits it, with the claim flattened into `claimData`
+#[allow(non_snake_case)]
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub struct ClaimInfo {
+pub struct SdkProof {
+    pub identifier: String,
+    pub claimData: SdkClaimData,
+    pub signatures: Vec<String>,
+    // the contract selects the witnesses from the epoch, these are informational
+    #[serde(default)]
+    pub witnesses: Vec<SdkWitness>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extractedParameterValues: Option<BTreeMap<String, String>>,
+}
+
+#[allow(non_snake_case)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SdkClaimData {
     pub provider: String,
+    #[serde(deserialize_with = "deserialize_json_text")]
+    #[schemars(with = "JsonValue")]
     pub parameters: String,
+    pub owner: String,
+    pub timestampS: u64,
+    #[serde(deserialize_with = "deserialize_json_text")]
+    #[schemars(with = "JsonValue")]
     pub context: String,
+    pub identifier: String,
+    pub epoch: u64,
 }
 
-impl ClaimInfo {
-    pub fn hash(&self) -> String {
-        let mut hasher = Keccak256::new();
-        let hash_str = format!(
-            "{}\n{}\n{}",
-            &self.provider, &self.parameters, &self.context
-        );
-        hasher.update(&hash_str);
-
-        let hash = hasher.finalize().to_vec();
-        append_0x(hex::encode(hash).as_str())
-    }
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SdkWitness {
+    pub id: String,
+    pub url: String,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-pub struct CompleteClaimData {
-    pub identifier: String,
-    pub owner: String,
-    pub epoch: u64,
-    pub timestampS: u64,
+impl TryFrom<SdkProof> for Proof {
+    type Error = ContractError;
+
+    fn try_from(proof: SdkProof) -> Result<Self, Self::Error> {
+        let SdkProof {
+            identifier,
+            claimData: data,
+            signatures,
+            extractedParameterValues: extracted,
+            ..
+        } = proof;
+        if identifier != data.identifier {
+            return Err(ContractError::MalformedProof {
+                reason: format!(
+                    "identifier {} differs from the claimData identifier {}",
+                    identifier, data.identifier
+                ),
+            });
+        }
+
+        let claim_info = ClaimInfo {
+            provider: data.provider,
+            parameters: data.parameters,
+            context: data.context,
+        };
+        // The extracted values are not signed, they have to be the ones in the signed context
+        if let Some(extracted) = extracted {
+            if extracted != claim_info.parse_context()?.extractedParameters {
+                return Err(ContractError::MalformedProof {
+                    reason: "extractedParameterValues differ from the claim context".to_string(),
+                });
+            }
+        }
+
+        Ok(Proof {
+            claimInfo: claim_info,
+            signedClaim: SignedClaim {
+                claim: CompleteClaimData {
+                    identifier,
+                    owner: data.owner,
+                    epoch: data.epoch,
+                    timestampS: data.timestampS,
+                },
+                signatures,
+            },
+        })
+    }
 }
 
-impl CompleteClaimData {
-    pub fn serialise(&self) -> String {
-        format!(
-            "{}\n{}\n{}\n{}",
-            &self.identifier,
-            &self.owner.to_string(),
-            &self.timestampS.to_string(),
-            &self.epoch.to_string()
-        )
+impl From<Proof> for SdkProof {
+    fn from(proof: Proof) -> Self {
+        let Proof {
+            claimInfo: info,
+            signedClaim: signed,
+        } = proof;
+        let extracted = info
+            .parse_context()
+            .ok()
+            .map(|context| context.extractedParameters);
+        SdkProof {
+            identifier: signed.claim.identifier.clone(),
+            claimData: SdkClaimData {
+                provider: info.provider,
+                parameters: info.parameters,
+                owner: signed.claim.owner,
+                timestampS: signed.claim.timestampS,
+                context: info.context,
+                identifier: signed.claim.identifier,
+                epoch: signed.claim.epoch,
+            },
+            signatures: signed.signatures,
+            witnesses: vec![],
+            extractedParameterValues: extracted,
+        }
     }
 }
 
+/// The proof formats `ProofMsg` accepts
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub struct SignedClaim {
-    pub claim: CompleteClaimData,
-    pub signatures: Vec<String>,
+#[serde(untagged)]
+pub enum ProofFormat {
+    Reclaim(Proof),
+    Sdk(SdkProof),
 }
 
-impl SignedClaim {
-    pub fn recover_signers_of_signed_claim(
-        self,
-        _deps: DepsMut,
-    ) -> Result<Vec<String>, ContractError> {
-        use crate::claims::identity_digest::Identity256;
-        use digest::Update;
-        // Create empty array
-        let mut expected = vec![];
-        // Hash the signature
-        let serialised_claim = self.claim.serialise();
-
-        let bm = keccak256(serialised_claim.as_str());
-        let message_hash = bm.to_vec();
-
-        // For each signature in the claim
-        for mut complete_signature in self.signatures {
-            complete_signature.remove(0);
-            complete_signature.remove(0);
-            let rec_param = complete_signature
-                .get((complete_signature.len() as usize - 2)..(complete_signature.len() as usize))
-                .unwrap();
-            let mut mut_sig_str = complete_signature.clone();
-            mut_sig_str.pop();
-            mut_sig_str.pop();
-
-            let rec_dec = hex::decode(rec_param).unwrap();
-            let rec_norm = rec_dec.first().unwrap() - 27;
-            let r_s = hex::decode(mut_sig_str).unwrap();
-
-            let id = match rec_norm {
-                0 => RecoveryId::new(false, false),
-                1 => RecoveryId::new(true, false),
-                _ => return Err(ContractError::SignatureErr {}),
-            };
-
-            let signature = Signature::from_bytes(r_s.as_slice().into()).unwrap();
-            let message_digest = Identity256::new().chain(&message_hash);
-
-            // Recover the public key
-            let verkey = VerifyingKey::recover_from_digest(message_digest, &signature, id).unwrap();
-            let key: Vec<u8> = verkey.to_encoded_point(false).as_bytes().into();
-            let hasher = Keccak256::new_with_prefix(&key[1..]);
-
-            let hash = hasher.finalize().to_vec();
-
-            let address_bytes = hash.get(12..).unwrap();
-            let public_key = append_0x(&hex::encode(address_bytes));
-            expected.push(public_key);
+impl TryFrom<ProofFormat> for Proof {
+    type Error = ContractError;
+
+    fn try_from(proof: ProofFormat) -> Result<Self, Self::Error> {
+        match proof {
+            ProofFormat::Reclaim(proof) => Ok(proof),
+            ProofFormat::Sdk(proof) => proof.try_into(),
         }
-        Ok(expected)
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
-#[serde(rename_all = "snake_case")]
-pub struct Proof {
-    pub claimInfo: ClaimInfo,
-    pub signedClaim: SignedClaim,
+/// Serde adapter reading a `Proof` from any `ProofFormat`
+pub fn deserialize_proof<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Proof, D::Error> {
+    let proof = ProofFormat::deserialize(deserializer)?;
+    Proof::try_from(proof).map_err(serde::de::Error::custom)
 }
@@ -1,25 +1,51 @@
 use {
-    crate::state::{get_all_epochs, CONFIG, EPOCHS},
+    crate::state::{
+        get_all_badges, get_all_epochs, get_badges_by_owner, get_collected_fees,
+        get_witness_rewards, BADGES, BADGES_BY_OWNER, BADGE_COUNT, COLLECTED_FEES, CONFIG,
+        PROVIDER_FEES, WITNESS_PAYOUTS, WITNESS_REWARDS,
+    },
     cosmwasm_std::entry_point,
     cosmwasm_std::to_json_binary,
     cosmwasm_std::{
-        Addr, Binary, Deps, DepsMut, Env, Event, MessageInfo, Response, StdResult, Timestamp,
-        Uint128,
+        Addr, BankMsg, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, Event, HexBinary,
+        MessageInfo, Response, StdResult, Storage, Timestamp, Uint128,
     },
 };
 
-use crate::state::{Epoch, Witness};
+use crate::claims::{recover_eth_signer, resolve_signer, ApiCrypto, Proof, MAX_CLAIM_TEXT_BYTES};
+use crate::ibc::{allow_ibc_peer, disallow_ibc_peer};
+use crate::state::{
+    get_all_domains, get_ibc_channels, get_ibc_peers, get_pending_epoch_changes, get_role_members,
+    has_role, load_epoch, may_load_epoch, save_epoch, Badge, BadgeAttribute, BadgeConfig, Domain,
+    DomainPolicy, Epoch, EpochChange, EpochPolicy, PauseScope, PauseState, PendingEpochChange,
+    Role, SignatureScheme, SizeLimits, UnknownSignerPolicy, Witness, DOMAINS, LAST_PENDING_ID,
+    PENDING_EPOCH_CHANGES, ROLES,
+};
 use crate::{error::ContractError, msg::GetAllEpochResponse};
 use crate::{
-    msg::{ExecuteMsg, GetEpochResponse, InstantiateMsg, ProofMsg, QueryMsg},
+    msg::{
+        AllNftInfoResponse, ContractInfoResponse, ExecuteMsg, GetAllDomainsResponse,
+        GetBadgeResponse, GetDomainResponse, GetEpochResponse, GetFeeConfigResponse,
+        GetIbcChannelsResponse, GetIbcPeersResponse, GetLimitsResponse, GetPauseStateResponse,
+        GetPendingEpochChangesResponse, GetProviderFeeResponse, GetRoleMembersResponse,
+        GetWitnessRewardsResponse, InstantiateMsg, NftInfoResponse, NumTokensResponse,
+        OwnerOfResponse, ProofMsg, QueryMsg, SudoMsg, TokensResponse,
+    },
     state::Config,
 };
-use sha2::{Digest, Sha256};
 
 // version info for migration info
 // const CONTRACT_NAME: &str = "crates.io:reclaim-cosmwasm";
 // const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// cw721 collection info of the badges
+pub const BADGE_NAME: &str = "Reclaim Badge";
+pub const BADGE_SYMBOL: &str = "RECLAIM";
+
+// page size of the cw721 token listings
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 100;
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -28,9 +54,21 @@ pub fn instantiate(
     msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     let addr = deps.api.addr_validate(&msg.owner)?;
+    let guardian = msg
+        .guardian
+        .map(|guardian| deps.api.addr_validate(&guardian))
+        .transpose()?;
     let config = Config {
         owner: addr,
         current_epoch: Uint128::zero(),
+        badge: msg.badge,
+        fee: None,
+        treasury: None,
+        witness_fee_share: Decimal::zero(),
+        paused: PauseState::default(),
+        guardian,
+        epoch_delay: 0,
+        limits: SizeLimits::default(),
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -46,23 +84,85 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::VerifyProof(msg) => verify_proof(deps, msg, env),
+        ExecuteMsg::VerifyProof(msg) => verify_proof(deps, msg, env, info),
         ExecuteMsg::AddEpoch {
             witness,
             minimum_witness,
-        } => add_epoch(deps, env, witness, minimum_witness, info.sender.clone()),
-    }
-}
-
-fn generate_random_seed(bytes: Vec<u8>, offset: usize) -> u32 {
-    // Convert the hash result into a u32 using the offset
-    let hash_slice = &bytes[offset..offset + 4];
-    let mut seed = 0u32;
-    for (i, &byte) in hash_slice.iter().enumerate() {
-        seed |= u32::from(byte) << (i * 8);
+            policy,
+            domain,
+        } => add_epoch(
+            deps,
+            env,
+            domain,
+            witness,
+            minimum_witness,
+            policy,
+            info.sender.clone(),
+        ),
+        ExecuteMsg::UpdateWitnesses {
+            witness,
+            minimum_witness,
+            policy,
+            domain,
+        } => update_witnesses(
+            deps,
+            env,
+            domain,
+            witness,
+            minimum_witness,
+            policy,
+            info.sender.clone(),
+        ),
+        ExecuteMsg::CreateDomain {
+            name,
+            admin,
+            policy,
+        } => create_domain(deps, name, admin, policy, info.sender.clone()),
+        ExecuteMsg::UpdateDomain {
+            name,
+            admin,
+            policy,
+        } => update_domain(deps, name, admin, policy, info.sender.clone()),
+        ExecuteMsg::SetEpochDelay { seconds } => {
+            set_epoch_delay(deps, env, seconds, info.sender.clone())
+        }
+        ExecuteMsg::ExecuteEpochChange { id } => execute_epoch_change(deps, env, id),
+        ExecuteMsg::CancelEpochChange { id } => cancel_epoch_change(deps, id, info.sender.clone()),
+        ExecuteMsg::UpdateBadgeConfig { badge } => {
+            update_badge_config(deps, badge, info.sender.clone())
+        }
+        ExecuteMsg::UpdateFeeConfig {
+            fee,
+            treasury,
+            witness_share,
+        } => update_fee_config(deps, fee, treasury, witness_share, info.sender.clone()),
+        ExecuteMsg::SetProviderFee { provider_hash, fee } => {
+            set_provider_fee(deps, provider_hash, fee, info.sender.clone())
+        }
+        ExecuteMsg::WithdrawFees {} => withdraw_fees(deps, info.sender.clone()),
+        ExecuteMsg::RegisterWitnessPayout {
+            witness,
+            signature,
+            domain,
+        } => register_witness_payout(deps, env, witness, signature, domain, info.sender.clone()),
+        ExecuteMsg::ClaimWitnessRewards { witness } => {
+            claim_witness_rewards(deps, witness, info.sender.clone())
+        }
+        ExecuteMsg::Pause { scope } => set_paused(deps, scope, true, info.sender.clone()),
+        ExecuteMsg::Unpause { scope } => set_paused(deps, scope, false, info.sender.clone()),
+        ExecuteMsg::UpdateGuardian { guardian } => {
+            update_guardian(deps, guardian, info.sender.clone())
+        }
+        ExecuteMsg::GrantRole { role, address } => {
+            grant_role(deps, role, address, info.sender.clone())
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            revoke_role(deps, role, address, info.sender.clone())
+        }
+        ExecuteMsg::UpdateLimits { limits } => update_limits(deps, limits, info.sender.clone()),
+        ExecuteMsg::AllowIbcPeer { peer } => allow_ibc_peer(deps, peer, info.sender.clone()),
+        ExecuteMsg::DisallowIbcPeer { peer } => disallow_ibc_peer(deps, peer, info.sender.clone()),
     }
-
-    seed
 }
 
 pub fn fetch_witness_for_claim(
@@ -70,466 +170,4294 @@ pub fn fetch_witness_for_claim(
     identifier: String,
     timestamp: Timestamp,
 ) -> Vec<Witness> {
-    let mut selected_witness = vec![];
-
-    // Create a hash from identifier+epoch+minimum+timestamp
-    let hash_str = format!(
-        "{}\n{}\n{}\n{}",
-        hex::encode(identifier),
-        epoch.minimum_witness_for_claim_creation.to_string(),
-        timestamp.nanos().to_string(),
-        epoch.id.to_string()
-    );
-    let result = hash_str.as_bytes().to_vec();
-    let mut hasher = Sha256::new();
-    hasher.update(result);
-    let hash_result = hasher.finalize().to_vec();
-    let witenesses_left_list = epoch.witness;
-    let mut byte_offset = 0;
-    let witness_left = witenesses_left_list.len();
-    for _i in 0..epoch.minimum_witness_for_claim_creation.into() {
-        let random_seed = generate_random_seed(hash_result.clone(), byte_offset) as usize;
-        let witness_index = random_seed % witness_left;
-        let witness = witenesses_left_list.get(witness_index);
-        match witness {
-            Some(data) => selected_witness.push(data.clone()),
-            None => {}
-        }
-        byte_offset = (byte_offset + 4) % hash_result.len();
-    }
-
-    selected_witness
-}
-
-pub fn verify_proof(deps: DepsMut, msg: ProofMsg, env: Env) -> Result<Response, ContractError> {
-    // Find the epoch from database
-    let epoch = EPOCHS.load(deps.storage, msg.proof.signedClaim.claim.epoch.into())?;
+    reclaim_verifier_core::fetch_witness_for_claim(
+        &epoch.witness,
+        epoch.minimum_witness_for_claim_creation.u128(),
+        epoch.id.u128(),
+        &identifier,
+        timestamp.nanos(),
+    )
+}
+
+pub fn verify_proof(
+    deps: DepsMut,
+    msg: ProofMsg,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.paused.verification {
+        return Err(ContractError::Paused {});
+    }
+    let epoch = check_claim(deps.as_ref(), &config, &msg.proof, msg.domain.as_deref())?;
     let mut resp = Response::new();
 
+    // Check the verification fee, and send back whatever was paid on top of it
+    let fee = required_fee(deps.as_ref(), &config, &msg.proof)?;
+    let (charged, refund) = check_fee(fee, &info.funds)?;
+    if !refund.is_empty() {
+        resp = resp.add_message(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: refund,
+        });
+    }
+
+    let counted_witness = check_witnesses(deps.as_ref(), &msg.proof, epoch, env.block.time)?;
+    for signed in &counted_witness {
+        let signed_event = Event::new("signer").add_attribute("sig", signed.clone());
+        resp = resp.add_event(signed_event);
+    }
+
+    // Share the fee between the witnesses that signed, the rest goes to the treasury
+    if let Some(charged) = charged {
+        distribute_fee(deps.storage, &config, charged, &counted_witness)?;
+    }
+
+    // Mint the soulbound badge to the claim owner if the mode is enabled
+    if let Some(badge_config) = config.badge {
+        match badge_owner(deps.as_ref(), &env, &msg, &info.sender)? {
+            Some(owner) => match mint_badge(deps, &env, &msg.proof, &badge_config, owner)? {
+                Some(badge) => {
+                    let badge_event = Event::new("badge")
+                        .add_attribute("token_id", badge.token_id)
+                        .add_attribute("owner", badge.owner);
+                    resp = resp.add_event(badge_event);
+                }
+                // verifying the claim again is fine, it just has its badge already
+                None => {
+                    let token_id = &msg.proof.signedClaim.claim.identifier;
+                    resp = resp.add_attribute("badge_already_minted", token_id);
+                }
+            },
+            None => {
+                let owner = &msg.proof.signedClaim.claim.owner;
+                resp = resp.add_attribute("badge_owner_not_proven", owner);
+            }
+        }
+    }
+    Ok(resp)
+}
+
+pub fn badge_owner_message(contract: &Addr, holder: &Addr) -> String {
+    format!("reclaim-xion badge\n{}\n{}", contract, holder)
+}
+
+// The badge goes to the claim owner, so a proof submitted by anyone else still mints to them.
+// An owner that is not an address on this chain names the sender as the holder by
+// personal-signing `badge_owner_message`, otherwise no badge is minted.
+fn badge_owner(
+    deps: Deps,
+    env: &Env,
+    msg: &ProofMsg,
+    sender: &Addr,
+) -> Result<Option<Addr>, ContractError> {
+    let owner = &msg.proof.signedClaim.claim.owner;
+    if let Ok(owner) = deps.api.addr_validate(owner) {
+        return Ok(Some(owner));
+    }
+    let Some(signature) = &msg.owner_signature else {
+        return Ok(None);
+    };
+
+    let message = badge_owner_message(&env.contract.address, sender);
+    let signature = HexBinary::from_hex(signature.trim_start_matches("0x"))
+        .map_err(|_| ContractError::SignatureErr {})?;
+    let signer = recover_eth_signer(&ApiCrypto(deps.api), &message, &signature)?;
+    if signer != owner.to_lowercase() {
+        return Err(ContractError::Unauthorized {});
+    }
+    Ok(Some(sender.clone()))
+}
+
+/// Checks a proof up to its signatures: its size, its epoch, its identifier and the domain
+/// policy. Returns the epoch the claim belongs to.
+pub fn check_claim(
+    deps: Deps,
+    config: &Config,
+    proof: &Proof,
+    domain: Option<&str>,
+) -> Result<Epoch, ContractError> {
+    check_proof_size(proof, &config.limits)?;
+
+    // Find the epoch from database
+    let domain_policy = match domain {
+        Some(name) => Some(load_domain(deps.storage, name)?.policy),
+        None => None,
+    };
+    let epoch_id = proof.signedClaim.claim.epoch.into();
+    let epoch = may_load_epoch(deps.storage, domain, epoch_id)?
+        .ok_or(ContractError::EpochNotFound { id: epoch_id })?;
+    if epoch.ended {
+        return Err(ContractError::EpochEnded {});
+    }
+
     // Hash the claims, and verify with identifier hash
-    let hashed = msg.proof.claimInfo.hash();
-    if msg.proof.signedClaim.claim.identifier != hashed {
-        return Err(ContractError::HashMismatchErr {});
+    let hashed = proof.claimInfo.hash();
+    if proof.signedClaim.claim.identifier != hashed {
+        return Err(ContractError::HashMismatchErr {
+            expected: proof.signedClaim.claim.identifier.clone(),
+            computed: hashed,
+        });
     }
 
-    // Fetch witness for claim
-    let expected_witness = fetch_witness_for_claim(
-        epoch,
-        msg.proof.signedClaim.claim.identifier.clone(),
-        env.block.time,
-    );
+    // Apply the domain policy
+    if let Some(policy) = domain_policy {
+        if !policy.allowed_providers.is_empty() {
+            let context = proof.claimInfo.parse_context()?;
+            if !policy.allowed_providers.contains(&context.providerHash) {
+                return Err(ContractError::ProviderNotAllowed {
+                    provider_hash: context.providerHash,
+                });
+            }
+        }
+    }
+    Ok(epoch)
+}
 
-    let expected_witness_addresses = Witness::get_addresses(expected_witness);
+/// Recovers the signers of a proof and checks them against the witnesses `epoch` selects
+/// for it at `time`, returning the signers that count towards the threshold
+pub fn check_witnesses(
+    deps: Deps,
+    proof: &Proof,
+    epoch: Epoch,
+    time: Timestamp,
+) -> Result<Vec<String>, ContractError> {
+    // Fetch witness for claim
+    let policy = epoch.policy.clone();
+    let expected_witness =
+        fetch_witness_for_claim(epoch, proof.signedClaim.claim.identifier.clone(), time);
 
-    // recover witness address from SignedClaims Object
-    let signed_witness = msg
-        .proof
+    // recover witness address from SignedClaims Object, per the scheme of each witness
+    let signed_witness = proof
         .signedClaim
-        .recover_signers_of_signed_claim(deps)?;
+        .resolve_signers(&ApiCrypto(deps.api), &expected_witness)?;
+
+    check_signers(&signed_witness, &expected_witness, &policy)
+}
 
-    // make sure the minimum requirement for witness is satisfied
-    if expected_witness_addresses.len() != signed_witness.len() {
-        return Err(ContractError::WitnessMismatchErr {});
+// Rejects oversized proofs before the claim is hashed or any signature recovered. The message
+// is already parsed by then, with its claim text capped at MAX_CLAIM_TEXT_BYTES before it was
+// canonicalized.
+fn check_proof_size(proof: &Proof, limits: &SizeLimits) -> Result<(), ContractError> {
+    let claim_info = &proof.claimInfo;
+    for (field, size, max) in [
+        (
+            "parameters",
+            claim_info.parameters.len(),
+            limits.max_parameters_bytes,
+        ),
+        (
+            "context",
+            claim_info.context.len(),
+            limits.max_context_bytes,
+        ),
+    ] {
+        if size > max as usize {
+            return Err(ContractError::InputTooLarge {
+                field: field.to_string(),
+                size,
+                max,
+            });
+        }
     }
+    let count = proof.signedClaim.signatures.len();
+    if count > limits.max_signatures as usize {
+        return Err(ContractError::TooManyItems {
+            field: "signatures".to_string(),
+            count,
+            max: limits.max_signatures,
+        });
+    }
+    Ok(())
+}
 
-    // Ensure for every signature in the sign, a expected witness exists from the database
-    for signed in signed_witness {
-        let signed_event = Event::new("signer").add_attribute("sig", signed.clone());
-        resp = resp.add_event(signed_event);
-        if !expected_witness_addresses.contains(&signed) {
-            return Err(ContractError::SignatureErr {});
+/// Checks the recovered signers of a claim against the witnesses selected for it and the
+/// epoch policy, returning the signers that count towards the threshold
+pub fn check_signers(
+    signed_witness: &[String],
+    expected_witness: &[Witness],
+    policy: &EpochPolicy,
+) -> Result<Vec<String>, ContractError> {
+    let mut expected_witness_addresses = Witness::get_addresses(expected_witness.to_vec());
+    // a witness can be selected more than once, but only signs once
+    expected_witness_addresses.sort();
+    expected_witness_addresses.dedup();
+
+    // Ensure for every signature in the sign, a expected witness exists from the database,
+    // and that no witness signed twice
+    let mut counted_witness: Vec<String> = vec![];
+    for (index, signed) in signed_witness.iter().enumerate() {
+        if signed_witness[..index].contains(signed) {
+            return Err(ContractError::DuplicateSigner {
+                address: signed.clone(),
+            });
+        }
+        if !expected_witness_addresses.contains(signed) {
+            match policy.unknown_signers {
+                UnknownSignerPolicy::Reject => {
+                    return Err(ContractError::UnexpectedSigner {
+                        address: signed.clone(),
+                        index,
+                    })
+                }
+                UnknownSignerPolicy::Ignore => continue,
+            }
         }
+        counted_witness.push(signed.clone());
     }
-    Ok(resp)
+
+    // make sure the threshold of witnesses is satisfied, capped at the distinct witnesses
+    // selected since one drawn twice still signs once,
+    // and that a claim is never accepted without any witness signing it
+    let threshold = match policy.threshold {
+        Some(threshold) => (threshold.u128() as usize).min(expected_witness_addresses.len()),
+        None => expected_witness_addresses.len(),
+    }
+    .max(1);
+    if counted_witness.len() < threshold {
+        return Err(ContractError::WitnessMismatchErr {
+            required: threshold,
+            signed: counted_witness.len(),
+        });
+    }
+    Ok(counted_witness)
+}
+
+fn required_fee(deps: Deps, config: &Config, proof: &Proof) -> Result<Option<Coin>, ContractError> {
+    // Only parse the claim context when there are provider specific fees to look up
+    if !PROVIDER_FEES.is_empty(deps.storage) {
+        let context = proof.claimInfo.parse_context()?;
+        if let Some(fee) = PROVIDER_FEES.may_load(deps.storage, &context.providerHash)? {
+            return Ok(Some(fee));
+        }
+    }
+    Ok(config.fee.clone())
+}
+
+// Returns the fee to charge and the coins to refund to the sender
+fn check_fee(
+    fee: Option<Coin>,
+    funds: &[Coin],
+) -> Result<(Option<Coin>, Vec<Coin>), ContractError> {
+    let fee = match fee {
+        Some(fee) if !fee.amount.is_zero() => fee,
+        _ => return Ok((None, funds.to_vec())),
+    };
+
+    let mut paid = Uint128::zero();
+    for coin in funds {
+        if coin.denom != fee.denom {
+            return Err(ContractError::WrongDenom {
+                expected: fee.denom,
+                received: coin.denom.clone(),
+            });
+        }
+        paid += coin.amount;
+    }
+    if paid < fee.amount {
+        return Err(ContractError::InsufficientFunds {
+            sent: Coin::new(paid, fee.denom.clone()),
+            required: fee,
+        });
+    }
+
+    let mut refund = vec![];
+    if paid > fee.amount {
+        refund.push(Coin::new(paid - fee.amount, fee.denom.clone()));
+    }
+    Ok((Some(fee), refund))
+}
+
+fn distribute_fee(
+    storage: &mut dyn Storage,
+    config: &Config,
+    fee: Coin,
+    signers: &[String],
+) -> Result<(), ContractError> {
+    let mut treasury_amount = fee.amount;
+    if !signers.is_empty() {
+        // Rounding leftovers stay with the treasury
+        let per_witness =
+            fee.amount.mul_floor(config.witness_fee_share) / Uint128::from(signers.len() as u128);
+        if !per_witness.is_zero() {
+            for signer in signers {
+                WITNESS_REWARDS.update(
+                    storage,
+                    (signer, &fee.denom),
+                    |rewards| -> StdResult<_> { Ok(rewards.unwrap_or_default() + per_witness) },
+                )?;
+                treasury_amount -= per_witness;
+            }
+        }
+    }
+
+    if !treasury_amount.is_zero() {
+        COLLECTED_FEES.update(storage, &fee.denom, |collected| -> StdResult<_> {
+            Ok(collected.unwrap_or_default() + treasury_amount)
+        })?;
+    }
+    Ok(())
+}
+
+fn mint_badge(
+    deps: DepsMut,
+    env: &Env,
+    proof: &Proof,
+    badge_config: &BadgeConfig,
+    owner: Addr,
+) -> Result<Option<Badge>, ContractError> {
+    // The claim identifier is the token id, so a claim can only ever mint once
+    let token_id = proof.signedClaim.claim.identifier.clone();
+    if BADGES.has(deps.storage, &token_id) {
+        return Ok(None);
+    }
+
+    let context = proof.claimInfo.parse_context()?;
+    let extracted_parameters = badge_config
+        .extracted_parameters
+        .iter()
+        .filter_map(|key| {
+            context
+                .extractedParameters
+                .get(key)
+                .map(|value| BadgeAttribute {
+                    key: key.clone(),
+                    value: value.clone(),
+                })
+        })
+        .collect();
+
+    let badge = Badge {
+        token_id: token_id.clone(),
+        owner,
+        provider_hash: context.providerHash,
+        extracted_parameters,
+        minted_at: env.block.time.nanos(),
+    };
+    BADGES.save(deps.storage, &token_id, &badge)?;
+    BADGES_BY_OWNER.save(deps.storage, (&badge.owner, &token_id), &Empty {})?;
+    let count = BADGE_COUNT.may_load(deps.storage)?.unwrap_or_default();
+    BADGE_COUNT.save(deps.storage, &(count + 1))?;
+    Ok(Some(badge))
 }
 
 // @dev - add epoch
 pub fn add_epoch(
     deps: DepsMut,
     env: Env,
+    domain: Option<String>,
     witness: Vec<Witness>,
     minimum_witness: Uint128,
+    policy: EpochPolicy,
     sender: Addr,
 ) -> Result<Response, ContractError> {
     // load configs
-    let mut config = CONFIG.load(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
 
-    // Check if sender is owner
-    if config.owner != sender {
+    // Check if sender holds the role, or administers the domain
+    if !can_manage_epochs(
+        deps.storage,
+        &config,
+        domain.as_deref(),
+        Role::EpochManager,
+        &sender,
+    )? {
         return Err(ContractError::Unauthorized {});
     }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
 
-    //Increment Epoch number
-    let new_epoch = config.current_epoch + Uint128::one();
-    // Create the new epoch
-    let epoch = Epoch {
-        id: new_epoch,
+    let mut change = EpochChange::AddEpoch {
         witness,
-        timestamp_start: env.block.time.nanos(),
-        timestamp_end: env.block.time.plus_days(1).nanos(),
-        minimum_witness_for_claim_creation: minimum_witness,
+        minimum_witness,
+        policy,
     };
+    validate_epoch_change(&mut change, &config.limits)?;
+
+    schedule_epoch_change(deps, env, &config, domain, change, sender)
+}
+
+// @dev - replace the witnesses of the current epoch
+pub fn update_witnesses(
+    deps: DepsMut,
+    env: Env,
+    domain: Option<String>,
+    witness: Vec<Witness>,
+    minimum_witness: Uint128,
+    policy: EpochPolicy,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
-    // Upsert the new epoch into memory
-    EPOCHS.update(
+    // Check if sender holds the role, or administers the domain
+    if !can_manage_epochs(
         deps.storage,
-        new_epoch.into(),
-        // we check if epoch with same id already exists for safety
-        |existsting| match existsting {
-            None => Ok(epoch),
-            Some(..) => Err(ContractError::AlreadyExists {}),
-        },
-    )?;
+        &config,
+        domain.as_deref(),
+        Role::WitnessManager,
+        &sender,
+    )? {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
 
-    // Save the new epoch
-    config.current_epoch = new_epoch;
-    CONFIG.save(deps.storage, &config)?;
-    Ok(Response::default())
+    // A queued change still targets this epoch once a newer one is added
+    let epoch_id = match domain.as_deref() {
+        Some(name) => load_domain(deps.storage, name)?.current_epoch,
+        None => config.current_epoch,
+    };
+    let mut change = EpochChange::UpdateWitnesses {
+        epoch_id,
+        witness,
+        minimum_witness,
+        policy,
+    };
+    validate_epoch_change(&mut change, &config.limits)?;
+
+    schedule_epoch_change(deps, env, &config, domain, change, sender)
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetEpoch { id } => to_json_binary(&query_epoch_id(deps, id)?),
-        QueryMsg::GetAllEpoch {} => to_json_binary(&query_all_epoch_ids(deps)?),
+// Also lowercases the witness addresses, the form signers, payouts and rewards are keyed by
+fn validate_epoch_change(
+    change: &mut EpochChange,
+    limits: &SizeLimits,
+) -> Result<(), ContractError> {
+    let (EpochChange::AddEpoch {
+        witness,
+        minimum_witness,
+        policy,
+    }
+    | EpochChange::UpdateWitnesses {
+        witness,
+        minimum_witness,
+        policy,
+        ..
+    }) = change
+    else {
+        return Ok(());
+    };
+
+    if witness.len() > limits.max_witnesses as usize {
+        return Err(ContractError::TooManyItems {
+            field: "witness".to_string(),
+            count: witness.len(),
+            max: limits.max_witnesses,
+        });
+    }
+    // selection draws the minimum from the witnesses, more than there are only burns gas
+    if minimum_witness.u128() > witness.len() as u128 {
+        return Err(ContractError::InvalidMinimumWitness {
+            minimum: minimum_witness.u128(),
+            count: witness.len(),
+        });
+    }
+    for witness in witness.iter_mut() {
+        witness.address.make_ascii_lowercase();
+        let key_address = witness.key_address();
+        if !witness.has_valid_key() || key_address.is_some_and(|a| a != witness.address) {
+            return Err(ContractError::InvalidWitnessKey {
+                address: witness.address.clone(),
+            });
+        }
     }
+    if let Some(threshold) = policy.threshold {
+        if threshold.is_zero() || threshold > *minimum_witness {
+            return Err(ContractError::InvalidThreshold {});
+        }
+    }
+    Ok(())
 }
 
-fn query_all_epoch_ids(deps: Deps) -> StdResult<GetAllEpochResponse> {
-    Ok(GetAllEpochResponse {
-        ids: get_all_epochs(deps.storage)?,
-    })
+// The default domain is managed through roles, named domains by their admin and the owner
+fn can_manage_epochs(
+    storage: &dyn Storage,
+    config: &Config,
+    domain: Option<&str>,
+    role: Role,
+    sender: &Addr,
+) -> Result<bool, ContractError> {
+    match domain {
+        None => Ok(has_role(storage, config, role, sender)),
+        Some(name) => {
+            let domain = load_domain(storage, name)?;
+            Ok(domain.admin == *sender || config.owner == *sender)
+        }
+    }
 }
 
-fn query_epoch_id(deps: Deps, id: u128) -> StdResult<GetEpochResponse> {
-    let data = EPOCHS.load(deps.storage, id)?;
-    Ok(GetEpochResponse { epoch: data })
+fn load_domain(storage: &dyn Storage, name: &str) -> Result<Domain, ContractError> {
+    DOMAINS
+        .may_load(storage, name)?
+        .ok_or_else(|| ContractError::DomainNotFound {
+            name: name.to_string(),
+        })
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::claims::{ClaimInfo, CompleteClaimData, Proof, SignedClaim};
-    use crate::state::{CONFIG, EPOCHS};
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{Addr, StdError, Uint128};
+fn validate_domain_name(name: &str) -> Result<(), ContractError> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+    if !valid {
+        return Err(ContractError::InvalidDomainName {});
+    }
+    Ok(())
+}
 
-    const OWNER: &str = "owner0000";
-    const USER: &str = "user0000";
-    const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
-    const RECLAIM_ADDRESS: &str = "0x244897572368eadf65bfbc5aec98d8e5443a9072";
+// @dev - register a new verifier domain
+pub fn create_domain(
+    deps: DepsMut,
+    name: String,
+    admin: String,
+    policy: DomainPolicy,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
-    // Helper to instantiate contract with default owner
-    fn setup_contract(deps: DepsMut) {
-        let msg = InstantiateMsg {
-            owner: OWNER.to_string(),
-        };
-        let info = mock_info(OWNER, &[]);
-        instantiate(deps, mock_env(), info, msg).unwrap();
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
     }
 
-    fn create_test_epoch() -> Epoch {
-        Epoch {
-            id: Uint128::from(1u128),
-            witness: vec![Witness {
-                address: RECLAIM_ADDRESS.to_string(), // Signer's address
-                host: "https://valid-witness.com".to_string(),
-            }],
-            timestamp_start: 0,
-            timestamp_end: 0,
-            minimum_witness_for_claim_creation: Uint128::from(1u128),
-        }
+    validate_domain_name(&name)?;
+    let domain = Domain {
+        name: name.clone(),
+        admin: deps.api.addr_validate(&admin)?,
+        current_epoch: Uint128::zero(),
+        policy,
+    };
+    DOMAINS.update(deps.storage, &name, |existing| match existing {
+        None => Ok(domain.clone()),
+        Some(..) => Err(ContractError::DomainExists {}),
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "create_domain")
+        .add_attribute("domain", name)
+        .add_attribute("admin", domain.admin))
+}
+
+// @dev - hand over a domain or change its policy
+pub fn update_domain(
+    deps: DepsMut,
+    name: String,
+    admin: Option<String>,
+    policy: Option<DomainPolicy>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let mut domain = load_domain(deps.storage, &name)?;
+
+    // Check if sender is the domain admin or owner
+    if domain.admin != sender && config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
     }
 
-    fn create_proof_msg() -> ProofMsg {
-        ProofMsg {proof: Proof{
-            claimInfo: ClaimInfo {
-                provider: "http".to_string(),
-                parameters: r#"{"additionalClientOptions":{},"body":"","geoLocation":"IN","headers":{"Sec-Fetch-Mode":"same-origin","Sec-Fetch-Site":"same-origin","User-Agent":"Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36"},"method":"GET","paramValues":{"URL_PARAMS_1":"xWw45l6nX7DP2FKRyePXSw","URL_PARAM_2_GRD":"variables=%7B%22screen_name%22%3A%22burnt9507278342%22%7D&features=%7B%22hidden_profile_subscriptions_enabled%22%3Atrue%2C%22profile_label_improvements_pcf_label_in_post_enabled%22%3Atrue%2C%22rweb_tipjar_consumption_enabled%22%3Atrue%2C%22verified_phone_label_enabled%22%3Afalse%2C%22subscriptions_verification_info_is_identity_verified_enabled%22%3Atrue%2C%22subscriptions_verification_info_verified_since_enabled%22%3Atrue%2C%22highlights_tweets_tab_ui_enabled%22%3Atrue%2C%22responsive_web_twitter_article_notes_tab_enabled%22%3Atrue%2C%22subscriptions_feature_can_gift_premium%22%3Atrue%2C%22creator_subscriptions_tweet_preview_api_enabled%22%3Atrue%2C%22responsive_web_graphql_skip_user_profile_image_extensions_enabled%22%3Afalse%2C%22responsive_web_graphql_timeline_navigation_enabled%22%3Atrue%7D&fieldToggles=%7B%22withAuxiliaryUserLabels%22%3Atrue%7D","URL_PARAM_DOMAIN":"x","created_at":"Wed Apr 23 16:06:50 +0000 2025","followers_count":"0","screen_name":"Burnt9507278342"},"responseMatches":[{"invert":false,"type":"contains","value":"\"screen_name\":\"{{screen_name}}\""},{"invert":false,"type":"contains","value":"\"followers_count\":{{followers_count}}"},{"invert":false,"type":"contains","value":"\"created_at\":\"{{created_at}}\""}],"responseRedactions":[{"jsonPath":"$.data.user.result.core.screen_name","regex":"\"screen_name\":\"(.*)\"","xPath":""},{"jsonPath":"$.data.user.result.legacy.followers_count","regex":"\"followers_count\":(.*)","xPath":""},{"jsonPath":"$.data.user.result.core.created_at","regex":"\"created_at\":\"(.*)\"","xPath":""}],"url":"https://{{URL_PARAM_DOMAIN}}.com/i/api/graphql/{{URL_PARAMS_1}}/UserByScreenName?{{URL_PARAM_2_GRD}}"}"#.to_string(),
-                context: r#"{"extractedParameters":{"URL_PARAMS_1":"xWw45l6nX7DP2FKRyePXSw","URL_PARAM_2_GRD":"variables=%7B%22screen_name%22%3A%22burnt9507278342%22%7D&features=%7B%22hidden_profile_subscriptions_enabled%22%3Atrue%2C%22profile_label_improvements_pcf_label_in_post_enabled%22%3Atrue%2C%22rweb_tipjar_consumption_enabled%22%3Atrue%2C%22verified_phone_label_enabled%22%3Afalse%2C%22subscriptions_verification_info_is_identity_verified_enabled%22%3Atrue%2C%22subscriptions_verification_info_verified_since_enabled%22%3Atrue%2C%22highlights_tweets_tab_ui_enabled%22%3Atrue%2C%22responsive_web_twitter_article_notes_tab_enabled%22%3Atrue%2C%22subscriptions_feature_can_gift_premium%22%3Atrue%2C%22creator_subscriptions_tweet_preview_api_enabled%22%3Atrue%2C%22responsive_web_graphql_skip_user_profile_image_extensions_enabled%22%3Afalse%2C%22responsive_web_graphql_timeline_navigation_enabled%22%3Atrue%7D&fieldToggles=%7B%22withAuxiliaryUserLabels%22%3Atrue%7D","URL_PARAM_DOMAIN":"x","created_at":"Wed Apr 23 16:06:50 +0000 2025","followers_count":"0","screen_name":"Burnt9507278342"},"providerHash":"0xd4fb71de874115b581e7c15fedd0f71b38fbfabf6894487d275fde2cca1d0ebb"}"#.to_string(),
-                 },
-            signedClaim: SignedClaim {
-                claim: CompleteClaimData {
-                    identifier: "0x5fba1c86439db035389d90f8025739c54849db4cfb7cf91aa3fb02abd9c1f83a".to_string(),
-                    owner: "0x612c00c6d44fa281beeea91805349519ef3c3e83".to_string(),
-                    epoch: 1_u64,
-                    timestampS: 1748539856,
-                },
-                signatures: vec![
-                    "0x04fac06fb875a8a4896912461655f039b9b7726b1eacc1727f4b87c04b3971951387dc60b884e80e5c866722c1e34738a41c163f6c6bca2e33759a5ed34538201b".to_string()
-                ],
-            },
-        }
+    if let Some(admin) = admin {
+        domain.admin = deps.api.addr_validate(&admin)?;
     }
+    if let Some(policy) = policy {
+        domain.policy = policy;
     }
+    DOMAINS.save(deps.storage, &name, &domain)?;
 
-    #[test]
-    fn proper_initialization() {
-        let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {
-            owner: OWNER.to_string(),
-        };
-        let info = mock_info("creator", &[]);
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+    Ok(Response::new()
+        .add_attribute("action", "update_domain")
+        .add_attribute("domain", name)
+        .add_attribute("admin", domain.admin))
+}
 
-        assert_eq!(0, res.messages.len());
+// @dev - change the timelock on epoch changes
+pub fn set_epoch_delay(
+    deps: DepsMut,
+    env: Env,
+    seconds: u64,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
 
-        let config = CONFIG.load(&deps.storage).unwrap();
-        assert_eq!(config.owner, Addr::unchecked(OWNER));
-        assert_eq!(config.current_epoch, Uint128::zero());
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
     }
 
-    #[test]
-    fn initialization_with_invalid_owner() {
-        let mut deps = mock_dependencies();
-        let msg = InstantiateMsg {
-            owner: "x".repeat(1000).to_string(), // Invalid address format
-        };
-        let info = mock_info("creator", &[]);
-        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+    // Lengthening the delay is always safe, shortening it has to wait out the current delay
+    let change = EpochChange::SetEpochDelay { seconds };
+    if seconds >= config.epoch_delay {
+        return apply_epoch_change(deps.storage, &env, None, change);
+    }
+    schedule_epoch_change(deps, env, &config, None, change, sender)
+}
 
-        assert!(res.is_err());
-        assert_eq!(
-            res.unwrap_err(),
-            ContractError::Std(StdError::generic_err("Invalid input: human address too long for this mock implementation (must be <= 90)."))
-        );
+fn schedule_epoch_change(
+    deps: DepsMut,
+    env: Env,
+    config: &Config,
+    domain: Option<String>,
+    change: EpochChange,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    if config.epoch_delay == 0 {
+        return apply_epoch_change(deps.storage, &env, domain.as_deref(), change);
     }
 
-    #[test]
-    fn add_epoch_successfully() {
-        let mut deps = mock_dependencies();
-        setup_contract(deps.as_mut());
-        let env = mock_env();
+    let id = LAST_PENDING_ID.may_load(deps.storage)?.unwrap_or_default() + 1;
+    let pending = PendingEpochChange {
+        id,
+        domain,
+        change,
+        proposer: sender,
+        executable_after: env.block.time.plus_seconds(config.epoch_delay).nanos(),
+    };
+    LAST_PENDING_ID.save(deps.storage, &id)?;
+    PENDING_EPOCH_CHANGES.save(deps.storage, id, &pending)?;
 
-        let witnesses = vec![
-            Witness {
-                address: ZERO_ADDRESS.to_string(),
-                host: "https://w1.com".to_string(),
-            },
+    Ok(Response::new()
+        .add_attribute("action", "queue_epoch_change")
+        .add_attribute("pending_id", id.to_string())
+        .add_attribute("executable_after", pending.executable_after.to_string()))
+}
+
+fn apply_epoch_change(
+    storage: &mut dyn Storage,
+    env: &Env,
+    domain: Option<&str>,
+    change: EpochChange,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(storage)?;
+    let mut named_domain = domain.map(|name| load_domain(storage, name)).transpose()?;
+    let current_epoch = match &named_domain {
+        Some(named_domain) => named_domain.current_epoch,
+        None => config.current_epoch,
+    };
+    let resp = match change {
+        EpochChange::AddEpoch {
+            witness,
+            minimum_witness,
+            policy,
+        } => {
+            //Increment Epoch number
+            let new_epoch = current_epoch + Uint128::one();
+            // Create the new epoch
+            let epoch = Epoch {
+                id: new_epoch,
+                witness,
+                timestamp_start: env.block.time.nanos(),
+                timestamp_end: env.block.time.plus_days(1).nanos(),
+                minimum_witness_for_claim_creation: minimum_witness,
+                ended: false,
+                policy,
+            };
+
+            // we check if epoch with same id already exists for safety
+            if may_load_epoch(storage, domain, new_epoch.into())?.is_some() {
+                return Err(ContractError::AlreadyExists {});
+            }
+            save_epoch(storage, domain, &epoch)?;
+
+            // Save the new epoch
+            match &mut named_domain {
+                Some(named_domain) => named_domain.current_epoch = new_epoch,
+                None => config.current_epoch = new_epoch,
+            }
+            let resp = Response::new()
+                .add_attribute("action", "add_epoch")
+                .add_attribute("epoch_id", new_epoch);
+            match domain {
+                Some(domain) => resp.add_attribute("domain", domain),
+                None => resp,
+            }
+        }
+        EpochChange::UpdateWitnesses {
+            epoch_id,
+            witness,
+            minimum_witness,
+            policy,
+        } => {
+            let mut epoch = may_load_epoch(storage, domain, epoch_id.into())?.ok_or(
+                ContractError::EpochNotFound {
+                    id: epoch_id.u128(),
+                },
+            )?;
+            epoch.witness = witness;
+            epoch.minimum_witness_for_claim_creation = minimum_witness;
+            epoch.policy = policy;
+            save_epoch(storage, domain, &epoch)?;
+            Response::new()
+                .add_attribute("action", "update_witnesses")
+                .add_attribute("epoch_id", epoch.id)
+        }
+        EpochChange::SetEpochDelay { seconds } => {
+            config.epoch_delay = seconds;
+            Response::new()
+                .add_attribute("action", "set_epoch_delay")
+                .add_attribute("seconds", seconds.to_string())
+        }
+    };
+
+    CONFIG.save(storage, &config)?;
+    if let Some(named_domain) = named_domain {
+        DOMAINS.save(storage, &named_domain.name, &named_domain)?;
+    }
+    Ok(resp)
+}
+
+// @dev - apply a queued epoch change once its delay has passed, callable by anyone
+pub fn execute_epoch_change(deps: DepsMut, env: Env, id: u64) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+
+    let pending = PENDING_EPOCH_CHANGES
+        .may_load(deps.storage, id)?
+        .ok_or(ContractError::PendingChangeNotFound { id })?;
+    if env.block.time.nanos() < pending.executable_after {
+        return Err(ContractError::TimelockNotExpired {
+            executable_after: pending.executable_after,
+        });
+    }
+
+    PENDING_EPOCH_CHANGES.remove(deps.storage, id);
+    apply_epoch_change(
+        deps.storage,
+        &env,
+        pending.domain.as_deref(),
+        pending.change,
+    )
+}
+
+// @dev - drop a queued epoch change
+pub fn cancel_epoch_change(
+    deps: DepsMut,
+    id: u64,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is owner or guardian
+    if config.owner != sender && config.guardian.as_ref() != Some(&sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !PENDING_EPOCH_CHANGES.has(deps.storage, id) {
+        return Err(ContractError::PendingChangeNotFound { id });
+    }
+
+    PENDING_EPOCH_CHANGES.remove(deps.storage, id);
+    Ok(Response::new()
+        .add_attribute("action", "cancel_epoch_change")
+        .add_attribute("pending_id", id.to_string()))
+}
+
+// @dev - enable, change or disable badge minting
+pub fn update_badge_config(
+    deps: DepsMut,
+    badge: Option<BadgeConfig>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+
+    config.badge = badge;
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::default())
+}
+
+// @dev - change the input size limits
+pub fn update_limits(
+    deps: DepsMut,
+    limits: SizeLimits,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+    validate_limits(&limits)?;
+
+    config.limits = limits;
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new().add_attribute("action", "update_limits"))
+}
+
+// A zero limit rejects every proof or epoch, and claim text over MAX_CLAIM_TEXT_BYTES is
+// already rejected while the proof is parsed
+fn validate_limits(limits: &SizeLimits) -> Result<(), ContractError> {
+    let max_text = MAX_CLAIM_TEXT_BYTES as u32;
+    for (field, value, max) in [
+        (
+            "max_parameters_bytes",
+            limits.max_parameters_bytes,
+            max_text,
+        ),
+        ("max_context_bytes", limits.max_context_bytes, max_text),
+        ("max_signatures", limits.max_signatures, u32::MAX),
+        ("max_witnesses", limits.max_witnesses, u32::MAX),
+    ] {
+        if value == 0 || value > max {
+            return Err(ContractError::InvalidLimit {
+                field: field.to_string(),
+                min: 1,
+                max,
+            });
+        }
+    }
+    Ok(())
+}
+
+// @dev - set the default verification fee and the treasury receiving collected fees
+pub fn update_fee_config(
+    deps: DepsMut,
+    fee: Option<Option<Coin>>,
+    treasury: Option<Option<String>>,
+    witness_share: Option<Option<Decimal>>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Check if sender holds the role
+    if !has_role(deps.storage, &config, Role::FeeManager, &sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+    if let Some(witness_share) = witness_share {
+        let witness_share = witness_share.unwrap_or_default();
+        if witness_share > Decimal::one() {
+            return Err(ContractError::InvalidFeeShare {});
+        }
+        config.witness_fee_share = witness_share;
+    }
+    if let Some(fee) = fee {
+        config.fee = fee;
+    }
+    if let Some(treasury) = treasury {
+        config.treasury = treasury
+            .map(|treasury| deps.api.addr_validate(&treasury))
+            .transpose()?;
+    }
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::default())
+}
+
+// @dev - override the verification fee for claims of one provider
+pub fn set_provider_fee(
+    deps: DepsMut,
+    provider_hash: String,
+    fee: Option<Coin>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Check if sender holds the role
+    if !has_role(deps.storage, &config, Role::FeeManager, &sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+
+    match fee {
+        Some(fee) => PROVIDER_FEES.save(deps.storage, &provider_hash, &fee)?,
+        None => PROVIDER_FEES.remove(deps.storage, &provider_hash),
+    }
+    Ok(Response::default())
+}
+
+// @dev - send all collected fees to the treasury
+pub fn withdraw_fees(deps: DepsMut, sender: Addr) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Check if sender holds the role
+    if !has_role(deps.storage, &config, Role::FeeManager, &sender) {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+
+    let collected = get_collected_fees(deps.storage)?;
+    if collected.is_empty() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    for coin in &collected {
+        COLLECTED_FEES.remove(deps.storage, &coin.denom);
+    }
+
+    let treasury = config.treasury.unwrap_or(config.owner);
+    Ok(Response::new()
+        .add_attribute("action", "withdraw_fees")
+        .add_attribute("treasury", treasury.as_str())
+        .add_message(BankMsg::Send {
+            to_address: treasury.to_string(),
+            amount: collected,
+        }))
+}
+
+// @dev - pause or unpause verification and/or admin actions
+pub fn set_paused(
+    deps: DepsMut,
+    scope: PauseScope,
+    paused: bool,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is guardian or holds the role
+    if config.guardian.as_ref() != Some(&sender)
+        && !has_role(deps.storage, &config, Role::Pauser, &sender)
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.paused.set(scope, paused);
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("action", if paused { "pause" } else { "unpause" })
+        .add_attribute("verification", config.paused.verification.to_string())
+        .add_attribute("admin", config.paused.admin.to_string()))
+}
+
+// @dev - set or remove the guardian
+pub fn update_guardian(
+    deps: DepsMut,
+    guardian: Option<String>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.guardian = guardian
+        .map(|guardian| deps.api.addr_validate(&guardian))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::default())
+}
+
+// @dev - give an address an admin role
+pub fn grant_role(
+    deps: DepsMut,
+    role: Role,
+    address: String,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    match role {
+        Role::Owner => {
+            config.owner = address.clone();
+            CONFIG.save(deps.storage, &config)?;
+        }
+        _ => ROLES.save(deps.storage, (role.as_str(), &address), &Empty {})?,
+    }
+    Ok(Response::new()
+        .add_attribute("action", "grant_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+// @dev - take an admin role away from an address
+pub fn revoke_role(
+    deps: DepsMut,
+    role: Role,
+    address: String,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+    if role == Role::Owner {
+        return Err(ContractError::CannotRevokeOwner {});
+    }
+
+    let address = deps.api.addr_validate(&address)?;
+    ROLES.remove(deps.storage, (role.as_str(), &address));
+    Ok(Response::new()
+        .add_attribute("action", "revoke_role")
+        .add_attribute("role", role.as_str())
+        .add_attribute("address", address))
+}
+
+pub fn payout_registration_message(contract: &Addr, payout: &Addr) -> String {
+    format!("reclaim-xion payout\n{}\n{}", contract, payout)
+}
+
+// @dev - witness proves ownership of its key to set where its rewards are paid
+pub fn register_witness_payout(
+    deps: DepsMut,
+    env: Env,
+    witness: String,
+    signature: String,
+    domain: Option<String>,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let address = witness.to_lowercase();
+    let config = CONFIG.load(deps.storage)?;
+    let epoch_id = match domain.as_deref() {
+        Some(name) => load_domain(deps.storage, name)?.current_epoch,
+        None => config.current_epoch,
+    };
+    // Witnesses sign with the scheme of their record in the current epoch of the domain,
+    // unknown ones with an Ethereum key
+    let witness = may_load_epoch(deps.storage, domain.as_deref(), epoch_id.u128())?
+        .and_then(|epoch| {
+            epoch
+                .witness
+                .into_iter()
+                .find(|witness| witness.address == address)
+        })
+        .unwrap_or(Witness {
+            address,
+            host: String::new(),
+            scheme: SignatureScheme::EthPersonalSign,
+            public_key: None,
+        });
+    let message = payout_registration_message(&env.contract.address, &sender);
+    let signature = HexBinary::from_hex(signature.trim_start_matches("0x"))
+        .map_err(|_| ContractError::SignatureErr {})?;
+    let signer = resolve_signer(
+        &ApiCrypto(deps.api),
+        &message,
+        &signature,
+        std::slice::from_ref(&witness),
+    )?;
+    if !signer.eq_ignore_ascii_case(&witness.address) {
+        return Err(ContractError::Unauthorized {});
+    }
+    // The first registration stands, so a record published later can't redirect the rewards
+    if let Some(existing) = WITNESS_PAYOUTS.may_load(deps.storage, &witness.address)? {
+        if existing != sender {
+            return Err(ContractError::PayoutAlreadyRegistered {});
+        }
+    }
+
+    WITNESS_PAYOUTS.save(deps.storage, &witness.address, &sender)?;
+    Ok(Response::new()
+        .add_attribute("action", "register_witness_payout")
+        .add_attribute("witness", signer)
+        .add_attribute("payout", sender))
+}
+
+// @dev - pay out the accumulated fee shares of a witness
+pub fn claim_witness_rewards(
+    deps: DepsMut,
+    witness: String,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let witness = witness.to_lowercase();
+    let payout = WITNESS_PAYOUTS
+        .may_load(deps.storage, &witness)?
+        .ok_or(ContractError::PayoutNotRegistered {})?;
+    if payout != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let rewards = get_witness_rewards(deps.storage, &witness)?;
+    if rewards.is_empty() {
+        return Err(ContractError::NothingToWithdraw {});
+    }
+    for coin in &rewards {
+        WITNESS_REWARDS.remove(deps.storage, (&witness, &coin.denom));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "claim_witness_rewards")
+        .add_attribute("witness", witness)
+        .add_message(BankMsg::Send {
+            to_address: payout.to_string(),
+            amount: rewards,
+        }))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::UpdateOwner { owner } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            config.owner = deps.api.addr_validate(&owner)?;
+            CONFIG.save(deps.storage, &config)?;
+            Ok(Response::new()
+                .add_attribute("action", "sudo_update_owner")
+                .add_attribute("owner", config.owner))
+        }
+        SudoMsg::AddEpoch {
+            witness,
+            minimum_witness,
+            policy,
+            domain,
+        } => {
+            let mut change = EpochChange::AddEpoch {
+                witness,
+                minimum_witness,
+                policy,
+            };
+            let config = CONFIG.load(deps.storage)?;
+            validate_epoch_change(&mut change, &config.limits)?;
+            apply_epoch_change(deps.storage, &env, domain.as_deref(), change)
+        }
+        SudoMsg::EndEpoch { id, domain } => sudo_end_epoch(deps, env, domain, id),
+        SudoMsg::Pause { scope } => sudo_set_paused(deps, scope, true),
+        SudoMsg::Unpause { scope } => sudo_set_paused(deps, scope, false),
+        SudoMsg::UpdateConfig {
+            guardian,
+            epoch_delay,
+            fee,
+            treasury,
+            witness_share,
+            badge,
+            limits,
+        } => {
+            let mut config = CONFIG.load(deps.storage)?;
+            if let Some(guardian) = guardian {
+                config.guardian = guardian
+                    .map(|guardian| deps.api.addr_validate(&guardian))
+                    .transpose()?;
+            }
+            if let Some(epoch_delay) = epoch_delay {
+                config.epoch_delay = epoch_delay;
+            }
+            if let Some(fee) = fee {
+                config.fee = fee;
+            }
+            if let Some(treasury) = treasury {
+                config.treasury = treasury
+                    .map(|treasury| deps.api.addr_validate(&treasury))
+                    .transpose()?;
+            }
+            if let Some(witness_share) = witness_share {
+                if witness_share > Decimal::one() {
+                    return Err(ContractError::InvalidFeeShare {});
+                }
+                config.witness_fee_share = witness_share;
+            }
+            if let Some(badge) = badge {
+                config.badge = badge;
+            }
+            if let Some(limits) = limits {
+                validate_limits(&limits)?;
+                config.limits = limits;
+            }
+            CONFIG.save(deps.storage, &config)?;
+            Ok(Response::new().add_attribute("action", "sudo_update_config"))
+        }
+    }
+}
+
+fn sudo_end_epoch(
+    deps: DepsMut,
+    env: Env,
+    domain: Option<String>,
+    id: u128,
+) -> Result<Response, ContractError> {
+    let domain = domain.as_deref();
+    if let Some(name) = domain {
+        load_domain(deps.storage, name)?;
+    }
+    let mut epoch =
+        may_load_epoch(deps.storage, domain, id)?.ok_or(ContractError::EpochNotFound { id })?;
+    epoch.ended = true;
+    epoch.timestamp_end = env.block.time.nanos();
+    save_epoch(deps.storage, domain, &epoch)?;
+    let resp = Response::new()
+        .add_attribute("action", "sudo_end_epoch")
+        .add_attribute("epoch_id", epoch.id);
+    match domain {
+        Some(domain) => Ok(resp.add_attribute("domain", domain)),
+        None => Ok(resp),
+    }
+}
+
+fn sudo_set_paused(
+    deps: DepsMut,
+    scope: PauseScope,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    config.paused.set(scope, paused);
+    CONFIG.save(deps.storage, &config)?;
+    Ok(Response::new()
+        .add_attribute("action", if paused { "sudo_pause" } else { "sudo_unpause" })
+        .add_attribute("verification", config.paused.verification.to_string())
+        .add_attribute("admin", config.paused.admin.to_string()))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetEpoch { id, domain } => to_json_binary(&query_epoch_id(deps, id, domain)?),
+        QueryMsg::GetAllEpoch { domain } => to_json_binary(&query_all_epoch_ids(deps, domain)?),
+        QueryMsg::GetDomain { name } => to_json_binary(&query_domain(deps, name)?),
+        QueryMsg::GetAllDomains {} => to_json_binary(&query_all_domains(deps)?),
+        QueryMsg::GetBadge { token_id } => to_json_binary(&query_badge(deps, token_id)?),
+        QueryMsg::OwnerOf { token_id, .. } => to_json_binary(&query_owner_of(deps, token_id)?),
+        QueryMsg::NftInfo { token_id } => to_json_binary(&query_nft_info(deps, token_id)?),
+        QueryMsg::AllNftInfo { token_id, .. } => to_json_binary(&AllNftInfoResponse {
+            access: query_owner_of(deps, token_id.clone())?,
+            info: query_nft_info(deps, token_id)?,
+        }),
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => to_json_binary(&query_tokens(deps, owner, start_after, limit)?),
+        QueryMsg::AllTokens { start_after, limit } => {
+            to_json_binary(&query_all_tokens(deps, start_after, limit)?)
+        }
+        QueryMsg::NumTokens {} => to_json_binary(&NumTokensResponse {
+            count: BADGE_COUNT.may_load(deps.storage)?.unwrap_or_default(),
+        }),
+        QueryMsg::ContractInfo {} => to_json_binary(&ContractInfoResponse {
+            name: BADGE_NAME.to_string(),
+            symbol: BADGE_SYMBOL.to_string(),
+        }),
+        QueryMsg::GetFeeConfig {} => to_json_binary(&query_fee_config(deps)?),
+        QueryMsg::GetProviderFee { provider_hash } => {
+            to_json_binary(&query_provider_fee(deps, provider_hash)?)
+        }
+        QueryMsg::GetWitnessRewards { witness } => {
+            to_json_binary(&query_witness_rewards(deps, witness)?)
+        }
+        QueryMsg::GetPauseState {} => to_json_binary(&query_pause_state(deps)?),
+        QueryMsg::GetRoleMembers { role } => to_json_binary(&query_role_members(deps, role)?),
+        QueryMsg::GetPendingEpochChanges {} => to_json_binary(&query_pending_epoch_changes(deps)?),
+        QueryMsg::GetLimits {} => to_json_binary(&GetLimitsResponse {
+            limits: CONFIG.load(deps.storage)?.limits,
+        }),
+        QueryMsg::GetIbcPeers {} => to_json_binary(&GetIbcPeersResponse {
+            peers: get_ibc_peers(deps.storage)?,
+        }),
+        QueryMsg::GetIbcChannels {} => to_json_binary(&GetIbcChannelsResponse {
+            channels: get_ibc_channels(deps.storage)?,
+        }),
+    }
+}
+
+fn query_all_epoch_ids(deps: Deps, domain: Option<String>) -> StdResult<GetAllEpochResponse> {
+    Ok(GetAllEpochResponse {
+        ids: get_all_epochs(deps.storage, domain.as_deref())?,
+    })
+}
+
+fn query_epoch_id(deps: Deps, id: u128, domain: Option<String>) -> StdResult<GetEpochResponse> {
+    let data = load_epoch(deps.storage, domain.as_deref(), id)?;
+    Ok(GetEpochResponse { epoch: data })
+}
+
+fn query_domain(deps: Deps, name: String) -> StdResult<GetDomainResponse> {
+    let domain = DOMAINS.load(deps.storage, &name)?;
+    Ok(GetDomainResponse { domain })
+}
+
+fn query_all_domains(deps: Deps) -> StdResult<GetAllDomainsResponse> {
+    Ok(GetAllDomainsResponse {
+        names: get_all_domains(deps.storage)?,
+    })
+}
+
+fn query_badge(deps: Deps, token_id: String) -> StdResult<GetBadgeResponse> {
+    let badge = BADGES.load(deps.storage, &token_id)?;
+    Ok(GetBadgeResponse { badge })
+}
+
+fn query_owner_of(deps: Deps, token_id: String) -> StdResult<OwnerOfResponse> {
+    let badge = BADGES.load(deps.storage, &token_id)?;
+    Ok(OwnerOfResponse {
+        owner: badge.owner.to_string(),
+        approvals: vec![],
+    })
+}
+
+fn query_nft_info(deps: Deps, token_id: String) -> StdResult<NftInfoResponse> {
+    let badge = BADGES.load(deps.storage, &token_id)?;
+    Ok(NftInfoResponse {
+        token_uri: None,
+        extension: badge.into(),
+    })
+}
+
+fn query_tokens(
+    deps: Deps,
+    owner: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    Ok(TokensResponse {
+        tokens: get_badges_by_owner(deps.storage, &owner, start_after.as_deref(), limit)?,
+    })
+}
+
+fn query_all_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    Ok(TokensResponse {
+        tokens: get_all_badges(deps.storage, start_after.as_deref(), limit)?,
+    })
+}
+
+fn query_fee_config(deps: Deps) -> StdResult<GetFeeConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(GetFeeConfigResponse {
+        fee: config.fee,
+        treasury: config.treasury.unwrap_or(config.owner),
+        witness_share: config.witness_fee_share,
+        collected: get_collected_fees(deps.storage)?,
+    })
+}
+
+fn query_pause_state(deps: Deps) -> StdResult<GetPauseStateResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(GetPauseStateResponse {
+        verification: config.paused.verification,
+        admin: config.paused.admin,
+        guardian: config.guardian,
+    })
+}
+
+fn query_role_members(deps: Deps, role: Role) -> StdResult<GetRoleMembersResponse> {
+    let members = match role {
+        Role::Owner => vec![CONFIG.load(deps.storage)?.owner],
+        _ => get_role_members(deps.storage, role)?,
+    };
+    Ok(GetRoleMembersResponse { members })
+}
+
+fn query_pending_epoch_changes(deps: Deps) -> StdResult<GetPendingEpochChangesResponse> {
+    Ok(GetPendingEpochChangesResponse {
+        delay: CONFIG.load(deps.storage)?.epoch_delay,
+        changes: get_pending_epoch_changes(deps.storage)?,
+    })
+}
+
+fn query_witness_rewards(deps: Deps, witness: String) -> StdResult<GetWitnessRewardsResponse> {
+    let witness = witness.to_lowercase();
+    Ok(GetWitnessRewardsResponse {
+        payout: WITNESS_PAYOUTS.may_load(deps.storage, &witness)?,
+        rewards: get_witness_rewards(deps.storage, &witness)?,
+    })
+}
+
+fn query_provider_fee(deps: Deps, provider_hash: String) -> StdResult<GetProviderFeeResponse> {
+    let fee = match PROVIDER_FEES.may_load(deps.storage, &provider_hash)? {
+        Some(fee) => Some(fee),
+        None => CONFIG.load(deps.storage)?.fee,
+    };
+    Ok(GetProviderFeeResponse { fee })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claims::{
+        append_0x, keccak256, ClaimInfo, CompleteClaimData, Proof, SdkProof, SignedClaim,
+    };
+    use crate::state::WITNESS_REWARDS;
+    use crate::state::{CONFIG, EPOCHS};
+    use crate::testing;
+    use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env, MockApi};
+    use cosmwasm_std::{coin, Addr, CosmosMsg, StdError, Uint128};
+    use k256::ecdsa::SigningKey;
+    use proptest::prelude::*;
+    use reclaim_verifier_core::claims::recover_eth_address;
+    use reclaim_verifier_core::{canonicalize_json, JsonValue};
+    use sha2::{Digest, Sha256};
+    use sha3::Keccak256;
+
+    const OWNER: &str = "owner0000";
+    const USER: &str = "user0000";
+    const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+    const RECLAIM_ADDRESS: &str = "0x244897572368eadf65bfbc5aec98d8e5443a9072";
+    const PROVIDER_HASH: &str =
+        "0xd4fb71de874115b581e7c15fedd0f71b38fbfabf6894487d275fde2cca1d0ebb";
+
+    // Helper to build a valid bech32 address from a readable name
+    fn addr(name: &str) -> Addr {
+        MockApi::default().addr_make(name)
+    }
+
+    // Helper to instantiate contract with default owner
+    fn setup_contract(deps: DepsMut) {
+        let msg = InstantiateMsg {
+            owner: addr(OWNER).to_string(),
+            badge: None,
+            guardian: None,
+        };
+        let info = message_info(&addr(OWNER), &[]);
+        instantiate(deps, mock_env(), info, msg).unwrap();
+    }
+
+    fn create_test_epoch() -> Epoch {
+        Epoch {
+            id: Uint128::from(1u128),
+            witness: vec![Witness {
+                address: RECLAIM_ADDRESS.to_string(), // Signer's address
+                host: "https://valid-witness.com".to_string(),
+                scheme: SignatureScheme::EthPersonalSign,
+                public_key: None,
+            }],
+            timestamp_start: 0,
+            timestamp_end: 0,
+            minimum_witness_for_claim_creation: Uint128::from(1u128),
+            ended: false,
+            policy: EpochPolicy::default(),
+        }
+    }
+
+    fn create_proof_msg() -> ProofMsg {
+        ProofMsg {proof: Proof{
+            claimInfo: ClaimInfo {
+                provider: "http".to_string(),
+                parameters: r#"{"additionalClientOptions":{},"body":"","geoLocation":"IN","headers":{"Sec-Fetch-Mode":"same-origin","Sec-Fetch-Site":"same-origin","User-Agent":"Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/123.0.0.0 Safari/537.36"},"method":"GET","paramValues":{"URL_PARAMS_1":"xWw45l6nX7DP2FKRyePXSw","URL_PARAM_2_GRD":"variables=%7B%22screen_name%22%3A%22burnt9507278342%22%7D&features=%7B%22hidden_profile_subscriptions_enabled%22%3Atrue%2C%22profile_label_improvements_pcf_label_in_post_enabled%22%3Atrue%2C%22rweb_tipjar_consumption_enabled%22%3Atrue%2C%22verified_phone_label_enabled%22%3Afalse%2C%22subscriptions_verification_info_is_identity_verified_enabled%22%3Atrue%2C%22subscriptions_verification_info_verified_since_enabled%22%3Atrue%2C%22highlights_tweets_tab_ui_enabled%22%3Atrue%2C%22responsive_web_twitter_article_notes_tab_enabled%22%3Atrue%2C%22subscriptions_feature_can_gift_premium%22%3Atrue%2C%22creator_subscriptions_tweet_preview_api_enabled%22%3Atrue%2C%22responsive_web_graphql_skip_user_profile_image_extensions_enabled%22%3Afalse%2C%22responsive_web_graphql_timeline_navigation_enabled%22%3Atrue%7D&fieldToggles=%7B%22withAuxiliaryUserLabels%22%3Atrue%7D","URL_PARAM_DOMAIN":"x","created_at":"Wed Apr 23 16:06:50 +0000 2025","followers_count":"0","screen_name":"Burnt9507278342"},"responseMatches":[{"invert":false,"type":"contains","value":"\"screen_name\":\"{{screen_name}}\""},{"invert":false,"type":"contains","value":"\"followers_count\":{{followers_count}}"},{"invert":false,"type":"contains","value":"\"created_at\":\"{{created_at}}\""}],"responseRedactions":[{"jsonPath":"$.data.user.result.core.screen_name","regex":"\"screen_name\":\"(.*)\"","xPath":""},{"jsonPath":"$.data.user.result.legacy.followers_count","regex":"\"followers_count\":(.*)","xPath":""},{"jsonPath":"$.data.user.result.core.created_at","regex":"\"created_at\":\"(.*)\"","xPath":""}],"url":"https://{{URL_PARAM_DOMAIN}}.com/i/api/graphql/{{URL_PARAMS_1}}/UserByScreenName?{{URL_PARAM_2_GRD}}"}"#.to_string(),
+                context: r#"{"extractedParameters":{"URL_PARAMS_1":"xWw45l6nX7DP2FKRyePXSw","URL_PARAM_2_GRD":"variables=%7B%22screen_name%22%3A%22burnt9507278342%22%7D&features=%7B%22hidden_profile_subscriptions_enabled%22%3Atrue%2C%22profile_label_improvements_pcf_label_in_post_enabled%22%3Atrue%2C%22rweb_tipjar_consumption_enabled%22%3Atrue%2C%22verified_phone_label_enabled%22%3Afalse%2C%22subscriptions_verification_info_is_identity_verified_enabled%22%3Atrue%2C%22subscriptions_verification_info_verified_since_enabled%22%3Atrue%2C%22highlights_tweets_tab_ui_enabled%22%3Atrue%2C%22responsive_web_twitter_article_notes_tab_enabled%22%3Atrue%2C%22subscriptions_feature_can_gift_premium%22%3Atrue%2C%22creator_subscriptions_tweet_preview_api_enabled%22%3Atrue%2C%22responsive_web_graphql_skip_user_profile_image_extensions_enabled%22%3Afalse%2C%22responsive_web_graphql_timeline_navigation_enabled%22%3Atrue%7D&fieldToggles=%7B%22withAuxiliaryUserLabels%22%3Atrue%7D","URL_PARAM_DOMAIN":"x","created_at":"Wed Apr 23 16:06:50 +0000 2025","followers_count":"0","screen_name":"Burnt9507278342"},"providerHash":"0xd4fb71de874115b581e7c15fedd0f71b38fbfabf6894487d275fde2cca1d0ebb"}"#.to_string(),
+                 },
+            signedClaim: SignedClaim {
+                claim: CompleteClaimData {
+                    identifier: "0x5fba1c86439db035389d90f8025739c54849db4cfb7cf91aa3fb02abd9c1f83a".to_string(),
+                    owner: "0x612c00c6d44fa281beeea91805349519ef3c3e83".to_string(),
+                    epoch: 1_u64,
+                    timestampS: 1748539856,
+                },
+                signatures: vec![
+                    "0x04fac06fb875a8a4896912461655f039b9b7726b1eacc1727f4b87c04b3971951387dc60b884e80e5c866722c1e34738a41c163f6c6bca2e33759a5ed34538201b".to_string()
+                ],
+            },
+        },
+        domain: None,
+        owner_signature: None,
+    }
+    }
+
+    #[test]
+    fn proper_initialization() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: addr(OWNER).to_string(),
+            badge: None,
+            guardian: None,
+        };
+        let info = message_info(&addr("creator"), &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(0, res.messages.len());
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, addr(OWNER));
+        assert_eq!(config.current_epoch, Uint128::zero());
+    }
+
+    #[test]
+    fn initialization_with_invalid_owner() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: "x".repeat(1000).to_string(), // Invalid address format
+            badge: None,
+            guardian: None,
+        };
+        let info = message_info(&addr("creator"), &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+
+        assert!(res.is_err());
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::Std(StdError::generic_err("Error decoding bech32"))
+        );
+    }
+
+    #[test]
+    fn add_epoch_successfully() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let env = mock_env();
+
+        let witnesses = vec![
+            Witness {
+                address: ZERO_ADDRESS.to_string(),
+                host: "https://w1.com".to_string(),
+                scheme: SignatureScheme::EthPersonalSign,
+                public_key: None,
+            },
+            Witness {
+                address: RECLAIM_ADDRESS.to_string(),
+                host: "https://w2.com".to_string(),
+                scheme: SignatureScheme::EthPersonalSign,
+                public_key: None,
+            },
+        ];
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::AddEpoch {
+            witness: witnesses.clone(),
+            minimum_witness: Uint128::from(2u128),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+
+        // First epoch
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // Verify state changes
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.current_epoch, Uint128::one());
+
+        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
+        assert_eq!(epoch.id, Uint128::one());
+        assert_eq!(epoch.witness, witnesses);
+        assert_eq!(
+            epoch.minimum_witness_for_claim_creation,
+            Uint128::from(2u128)
+        );
+        assert_eq!(epoch.timestamp_start, env.block.time.nanos());
+        assert_eq!(epoch.timestamp_end, env.block.time.plus_days(1).nanos());
+    }
+
+    #[test]
+    fn add_epoch_unauthorized() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let info = message_info(&addr(USER), &[]); // Non-owner
+        let msg = ExecuteMsg::AddEpoch {
+            witness: vec![],
+            minimum_witness: Uint128::from(1u128),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn epoch_id_increments_correctly() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let env = mock_env();
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::AddEpoch {
+            witness: vec![Witness {
+                address: ZERO_ADDRESS.to_string(),
+                host: "https://w.com".to_string(),
+                scheme: SignatureScheme::EthPersonalSign,
+                public_key: None,
+            }],
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+
+        // Add three epochs
+        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        execute(deps.as_mut(), env, info, msg).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.current_epoch, Uint128::from(3u128));
+
+        // Verify all epochs exist
+        assert!(EPOCHS.has(&deps.storage, 1));
+        assert!(EPOCHS.has(&deps.storage, 2));
+        assert!(EPOCHS.has(&deps.storage, 3));
+    }
+
+    #[test]
+    fn prevent_epoch_id_collision() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        // Manually create conflicting epoch
+        EPOCHS
+            .save(
+                &mut deps.storage,
+                1,
+                &Epoch {
+                    id: Uint128::one(),
+                    witness: vec![],
+                    timestamp_start: 0,
+                    timestamp_end: 0,
+                    minimum_witness_for_claim_creation: Uint128::zero(),
+                    ended: false,
+                    policy: EpochPolicy::default(),
+                },
+            )
+            .unwrap();
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::AddEpoch {
+            witness: vec![],
+            minimum_witness: Uint128::zero(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(res, Err(ContractError::AlreadyExists {}));
+    }
+
+    #[test]
+    fn add_epoch_with_zero_witnesses() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::AddEpoch {
+            witness: vec![],
+            minimum_witness: Uint128::zero(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
+        assert!(epoch.witness.is_empty());
+        assert_eq!(epoch.minimum_witness_for_claim_creation, Uint128::zero());
+    }
+
+    #[test]
+    fn add_epoch_with_minimum_above_witness_count() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::AddEpoch {
+            witness: create_test_epoch().witness,
+            minimum_witness: Uint128::new(5),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidMinimumWitness {
+                minimum: 5,
+                count: 1
+            })
+        );
+    }
+
+    #[test]
+    fn query_epoch_after_creation() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let witness = vec![Witness {
+            address: ZERO_ADDRESS.to_string(),
+            host: "https://query.com".to_string(),
+            scheme: SignatureScheme::EthPersonalSign,
+            public_key: None,
+        }];
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::AddEpoch {
+            witness: witness.clone(),
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // Query the epoch
+        let res = query_epoch_id(deps.as_ref(), 1, None).unwrap();
+        assert_eq!(res.epoch.id, Uint128::one());
+        assert_eq!(res.epoch.witness, witness);
+    }
+
+    #[test]
+    fn verify_proof_successfully() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        let owner = addr("owner0000");
+        let msg = InstantiateMsg {
+            owner: owner.to_string(),
+            badge: None,
+            guardian: None,
+        };
+        let info = message_info(&owner, &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        EPOCHS
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
+            .unwrap();
+
+        let proof = create_proof_msg();
+
+        // Verify the proof
+        let res = verify_proof(
+            deps.as_mut(),
+            proof,
+            env.clone(),
+            message_info(&addr(USER), &[]),
+        );
+
+        // Should succeed
+        assert!(res.is_ok());
+
+        // Check events contain the expected signer
+        let response = res.unwrap();
+        let signer_event = response.events.iter().find(|e| e.ty == "signer");
+        assert!(signer_event.is_some());
+        assert_eq!(signer_event.unwrap().attributes[0].value, RECLAIM_ADDRESS);
+    }
+
+    #[test]
+    fn verify_proof_with_hash_mismatch() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        let owner = addr("owner0000");
+        let msg = InstantiateMsg {
+            owner: owner.to_string(),
+            badge: None,
+            guardian: None,
+        };
+        let info = message_info(&owner, &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        EPOCHS
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
+            .unwrap();
+
+        // Create modified proof with invalid identifier
+        let mut proof = create_proof_msg();
+        proof.proof.signedClaim.claim.identifier = "invalid_hash".to_string();
+
+        // Verify should fail
+        let res = verify_proof(deps.as_mut(), proof, env, message_info(&addr(USER), &[]));
+        assert_eq!(
+            res,
+            Err(ContractError::HashMismatchErr {
+                expected: "invalid_hash".to_string(),
+                computed: create_proof_msg().proof.claimInfo.hash(),
+            })
+        );
+    }
+
+    #[test]
+    fn verify_proof_with_signature_error() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+
+        let owner = addr("owner0000");
+        let msg = InstantiateMsg {
+            owner: owner.to_string(),
+            badge: None,
+            guardian: None,
+        };
+        let info = message_info(&owner, &[]);
+        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        EPOCHS
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
+            .unwrap();
+
+        let mut proof = create_proof_msg();
+
+        // Just change the recovery id from "1b" to "1c"
+        proof.proof.signedClaim.signatures[0] = "0x04fac06fb875a8a4896912461655f039b9b7726b1eacc1727f4b87c04b3971951387dc60b884e80e5c866722c1e34738a41c163f6c6bca2e33759a5ed34538201c".to_string();
+
+        // Verify should fail
+        let res = verify_proof(deps.as_mut(), proof, env, message_info(&addr(USER), &[]));
+        assert_eq!(
+            res,
+            Err(ContractError::UnexpectedSigner {
+                address: "0x270ed4d4bbdd345d07d0a9fc7d57f6bc901d2787".to_string(),
+                index: 0,
+            })
+        );
+    }
+
+    // Enables badges and saves a single witness epoch, returning the proof of a claim by `owner`
+    fn setup_badge_claim(deps: DepsMut, owner: &str) -> ProofMsg {
+        let mut deps = deps;
+        setup_contract(deps.branch());
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::UpdateBadgeConfig {
+            badge: Some(BadgeConfig {
+                extracted_parameters: vec!["username".to_string(), "missing".to_string()],
+            }),
+        };
+        execute(deps.branch(), mock_env(), info, msg).unwrap();
+
+        let witnesses = testing::test_witnesses(1);
+        let epoch = testing::test_epoch(1, &witnesses, 1);
+        EPOCHS.save(deps.storage, 1, &epoch).unwrap();
+
+        let claim_info = testing::test_claim_info();
+        let mut claim = testing::test_claim(&claim_info, 1, 1748539856);
+        claim.owner = owner.to_string();
+        ProofMsg {
+            proof: testing::sign_proof(claim_info, claim, &[&witnesses[0]]),
+            domain: None,
+            owner_signature: None,
+        }
+    }
+
+    #[test]
+    fn verify_proof_mints_badge() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let proof = setup_badge_claim(deps.as_mut(), addr(USER).as_str());
+
+        // Whoever submits the proof, the badge belongs to the claim owner
+        let identifier = proof.proof.signedClaim.claim.identifier.clone();
+        let res = verify_proof(
+            deps.as_mut(),
+            proof.clone(),
+            env.clone(),
+            message_info(&addr("submitter"), &[]),
+        )
+        .unwrap();
+        let badge_event = res.events.iter().find(|e| e.ty == "badge").unwrap();
+        assert_eq!(badge_event.attributes[0].value, identifier);
+
+        let badge = query_badge(deps.as_ref(), identifier.clone())
+            .unwrap()
+            .badge;
+        assert_eq!(badge.owner, addr(USER));
+        assert_eq!(badge.provider_hash, testing::TEST_PROVIDER_HASH);
+        assert_eq!(
+            badge.extracted_parameters,
+            vec![BadgeAttribute {
+                key: "username".to_string(),
+                value: "reclaim".to_string(),
+            }]
+        );
+
+        let owned = query_tokens(deps.as_ref(), addr(USER).to_string(), None, None).unwrap();
+        assert_eq!(owned.tokens, vec![identifier.clone()]);
+        let submitted =
+            query_tokens(deps.as_ref(), addr("submitter").to_string(), None, None).unwrap();
+        assert!(submitted.tokens.is_empty());
+
+        // The same claim still verifies, but cannot mint a second badge
+        let res = verify_proof(deps.as_mut(), proof, env, message_info(&addr(OWNER), &[])).unwrap();
+        assert!(!res.events.iter().any(|e| e.ty == "badge"));
+        assert_eq!(res.attributes[0].key, "badge_already_minted");
+        assert_eq!(res.attributes[0].value, identifier);
+        let owned = query_tokens(deps.as_ref(), addr(USER).to_string(), None, None).unwrap();
+        assert_eq!(owned.tokens.len(), 1);
+    }
+
+    #[test]
+    fn owner_key_names_the_badge_holder() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let mut proof = setup_badge_claim(deps.as_mut(), &eth_address(&key));
+
+        // An owner that is not an address on this chain gets no badge without its signature
+        let res = verify_proof(
+            deps.as_mut(),
+            proof.clone(),
+            env.clone(),
+            message_info(&addr(USER), &[]),
+        )
+        .unwrap();
+        assert!(!res.events.iter().any(|e| e.ty == "badge"));
+        assert_eq!(res.attributes[0].key, "badge_owner_not_proven");
+        assert!(BADGES.is_empty(&deps.storage));
+
+        // The signature names its holder, a front-runner cannot reuse it
+        let message = badge_owner_message(&env.contract.address, &addr(USER));
+        proof.owner_signature = Some(eth_sign(&key, &message));
+        let res = verify_proof(
+            deps.as_mut(),
+            proof.clone(),
+            env.clone(),
+            message_info(&addr("front-runner"), &[]),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        verify_proof(
+            deps.as_mut(),
+            proof.clone(),
+            env,
+            message_info(&addr(USER), &[]),
+        )
+        .unwrap();
+        let badge = query_badge(deps.as_ref(), proof.proof.signedClaim.claim.identifier)
+            .unwrap()
+            .badge;
+        assert_eq!(badge.owner, addr(USER));
+    }
+
+    #[test]
+    fn badges_answer_cw721_queries() {
+        let mut deps = mock_dependencies();
+        let proof = setup_badge_claim(deps.as_mut(), addr(USER).as_str());
+        let token_id = proof.proof.signedClaim.claim.identifier.clone();
+        verify_proof(
+            deps.as_mut(),
+            proof,
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        )
+        .unwrap();
+
+        let run = |msg: QueryMsg| query(deps.as_ref(), mock_env(), msg);
+        let owner: OwnerOfResponse = cosmwasm_std::from_json(
+            run(QueryMsg::OwnerOf {
+                token_id: token_id.clone(),
+                include_expired: None,
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(owner.owner, addr(USER).to_string());
+        assert!(owner.approvals.is_empty());
+
+        let info: NftInfoResponse = cosmwasm_std::from_json(
+            run(QueryMsg::NftInfo {
+                token_id: token_id.clone(),
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(info.token_uri, None);
+        assert_eq!(info.extension.provider_hash, testing::TEST_PROVIDER_HASH);
+
+        let count: NumTokensResponse =
+            cosmwasm_std::from_json(run(QueryMsg::NumTokens {}).unwrap()).unwrap();
+        assert_eq!(count.count, 1);
+        let all: TokensResponse = cosmwasm_std::from_json(
+            run(QueryMsg::AllTokens {
+                start_after: None,
+                limit: None,
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(all.tokens, vec![token_id.clone()]);
+        let after: TokensResponse = cosmwasm_std::from_json(
+            run(QueryMsg::AllTokens {
+                start_after: Some(token_id),
+                limit: Some(1),
+            })
+            .unwrap(),
+        )
+        .unwrap();
+        assert!(after.tokens.is_empty());
+
+        let collection: ContractInfoResponse =
+            cosmwasm_std::from_json(run(QueryMsg::ContractInfo {}).unwrap()).unwrap();
+        assert_eq!(collection.symbol, BADGE_SYMBOL);
+        assert!(run(QueryMsg::NftInfo {
+            token_id: "0x00".to_string()
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn verify_proof_without_badge_config_mints_nothing() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        EPOCHS
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
+            .unwrap();
+
+        let res = verify_proof(
+            deps.as_mut(),
+            create_proof_msg(),
+            env,
+            message_info(&addr(USER), &[]),
+        )
+        .unwrap();
+        assert!(!res.events.iter().any(|e| e.ty == "badge"));
+        assert!(BADGES.is_empty(&deps.storage));
+    }
+
+    #[test]
+    fn update_badge_config_unauthorized() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let info = message_info(&addr(USER), &[]);
+        let msg = ExecuteMsg::UpdateBadgeConfig { badge: None };
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
+
+    // Helper to set up a contract with an epoch and a default fee of 100uxion
+    fn setup_contract_with_fee(deps: DepsMut) {
+        let mut deps = deps;
+        setup_contract(deps.branch());
+        EPOCHS.save(deps.storage, 1, &create_test_epoch()).unwrap();
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::UpdateFeeConfig {
+            fee: Some(Some(coin(100, "uxion"))),
+            treasury: Some(Some(addr("treasury").to_string())),
+            witness_share: None,
+        };
+        execute(deps, mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn verify_proof_charges_fee_and_refunds_overpayment() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        let info = message_info(&addr(USER), &[coin(150, "uxion")]);
+        let res = verify_proof(deps.as_mut(), create_proof_msg(), mock_env(), info).unwrap();
+
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: addr(USER).to_string(),
+                amount: vec![coin(50, "uxion")],
+            })
+        );
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.collected, vec![coin(100, "uxion")]);
+        assert_eq!(fees.treasury, addr("treasury"));
+    }
+
+    #[test]
+    fn verify_proof_with_insufficient_fee() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        let info = message_info(&addr(USER), &[coin(99, "uxion")]);
+        let res = verify_proof(deps.as_mut(), create_proof_msg(), mock_env(), info);
+        assert_eq!(
+            res,
+            Err(ContractError::InsufficientFunds {
+                required: coin(100, "uxion"),
+                sent: coin(99, "uxion"),
+            })
+        );
+
+        let info = message_info(&addr(USER), &[]);
+        let res = verify_proof(deps.as_mut(), create_proof_msg(), mock_env(), info);
+        assert_eq!(
+            res,
+            Err(ContractError::InsufficientFunds {
+                required: coin(100, "uxion"),
+                sent: coin(0, "uxion"),
+            })
+        );
+    }
+
+    #[test]
+    fn verify_proof_with_wrong_fee_denom() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        let info = message_info(&addr(USER), &[coin(100, "uatom")]);
+        let res = verify_proof(deps.as_mut(), create_proof_msg(), mock_env(), info);
+        assert_eq!(
+            res,
+            Err(ContractError::WrongDenom {
+                expected: "uxion".to_string(),
+                received: "uatom".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn verify_proof_uses_provider_fee() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::SetProviderFee {
+            provider_hash: PROVIDER_HASH.to_string(),
+            fee: Some(coin(5, "ufoo")),
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let fee = query_provider_fee(deps.as_ref(), PROVIDER_HASH.to_string()).unwrap();
+        assert_eq!(fee.fee, Some(coin(5, "ufoo")));
+
+        let info = message_info(&addr(USER), &[coin(5, "ufoo")]);
+        let res = verify_proof(deps.as_mut(), create_proof_msg(), mock_env(), info).unwrap();
+        assert!(res.messages.is_empty());
+
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.collected, vec![coin(5, "ufoo")]);
+    }
+
+    #[test]
+    fn withdraw_fees_to_treasury() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        let info = message_info(&addr(USER), &[coin(100, "uxion")]);
+        verify_proof(deps.as_mut(), create_proof_msg(), mock_env(), info).unwrap();
+
+        let info = message_info(&addr(USER), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::WithdrawFees {});
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let info = message_info(&addr(OWNER), &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info.clone(),
+            ExecuteMsg::WithdrawFees {},
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: addr("treasury").to_string(),
+                amount: vec![coin(100, "uxion")],
+            })
+        );
+
+        let res = execute(deps.as_mut(), mock_env(), info, ExecuteMsg::WithdrawFees {});
+        assert_eq!(res, Err(ContractError::NothingToWithdraw {}));
+    }
+
+    // Helper to personal-sign a message the way Ethereum wallets do
+    fn eth_sign(key: &SigningKey, message: &str) -> String {
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&keccak256(message)).unwrap();
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(27 + recovery_id.to_byte());
+        append_0x(&hex::encode(bytes))
+    }
+
+    fn eth_address(key: &SigningKey) -> String {
+        let point = key.verifying_key().to_encoded_point(false);
+        let hash = Keccak256::new_with_prefix(&point.as_bytes()[1..]).finalize();
+        append_0x(&hex::encode(&hash[12..]))
+    }
+
+    #[test]
+    fn verify_proof_shares_fee_with_signers() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::UpdateFeeConfig {
+            fee: Some(Some(coin(101, "uxion"))),
+            treasury: Some(None),
+            witness_share: Some(Some(Decimal::percent(50))),
+        };
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
+
+        // Leaving the share unset keeps it
+        let msg = ExecuteMsg::UpdateFeeConfig {
+            fee: None,
+            treasury: None,
+            witness_share: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.witness_share, Decimal::percent(50));
+
+        let info = message_info(&addr(USER), &[coin(101, "uxion")]);
+        verify_proof(deps.as_mut(), create_proof_msg(), mock_env(), info).unwrap();
+
+        let rewards = query_witness_rewards(deps.as_ref(), RECLAIM_ADDRESS.to_string()).unwrap();
+        assert_eq!(rewards.rewards, vec![coin(50, "uxion")]);
+        assert_eq!(rewards.payout, None);
+
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.collected, vec![coin(51, "uxion")]);
+        assert_eq!(fees.treasury, addr(OWNER));
+    }
+
+    #[test]
+    fn update_fee_config_with_invalid_share() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::UpdateFeeConfig {
+            fee: None,
+            treasury: None,
+            witness_share: Some(Some(Decimal::percent(101))),
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(res, Err(ContractError::InvalidFeeShare {}));
+    }
+
+    // Sends the fee manager's `update_fee_config` with the given JSON fields
+    fn update_fee_config_json(deps: DepsMut, fields: &str) {
+        let json = format!(r#"{{"update_fee_config":{{{fields}}}}}"#);
+        let msg: ExecuteMsg = cosmwasm_std::from_json(json).unwrap();
+        let info = message_info(&addr(OWNER), &[]);
+        execute(deps, mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    fn update_fee_config_unset_fee_keeps_it_and_null_clears_it() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        update_fee_config_json(deps.as_mut(), r#""witness_share":"0.5""#);
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.fee, Some(coin(100, "uxion")));
+
+        update_fee_config_json(deps.as_mut(), r#""fee":null"#);
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.fee, None);
+        assert_eq!(fees.treasury, addr("treasury"));
+    }
+
+    #[test]
+    fn update_fee_config_unset_treasury_keeps_it_and_null_clears_it() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        update_fee_config_json(deps.as_mut(), r#""fee":{"denom":"uxion","amount":"5"}"#);
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.treasury, addr("treasury"));
+
+        // Without a treasury, fees are withdrawn to the owner
+        update_fee_config_json(deps.as_mut(), r#""treasury":null"#);
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.treasury, addr(OWNER));
+        assert_eq!(fees.fee, Some(coin(5, "uxion")));
+    }
+
+    #[test]
+    fn update_fee_config_unset_share_keeps_it_and_null_clears_it() {
+        let mut deps = mock_dependencies();
+        setup_contract_with_fee(deps.as_mut());
+
+        update_fee_config_json(deps.as_mut(), r#""witness_share":"0.5""#);
+        update_fee_config_json(deps.as_mut(), r#""fee":{"denom":"uxion","amount":"5"}"#);
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.witness_share, Decimal::percent(50));
+
+        update_fee_config_json(deps.as_mut(), r#""witness_share":null"#);
+        let fees = query_fee_config(deps.as_ref()).unwrap();
+        assert_eq!(fees.witness_share, Decimal::zero());
+        assert_eq!(fees.fee, Some(coin(5, "uxion")));
+    }
+
+    #[test]
+    fn claim_witness_rewards_to_payout() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let env = mock_env();
+
+        let key = SigningKey::from_slice(&[7u8; 32]).unwrap();
+        let witness = eth_address(&key);
+        WITNESS_REWARDS
+            .save(
+                deps.as_mut().storage,
+                (&witness, "uxion"),
+                &Uint128::new(40),
+            )
+            .unwrap();
+
+        // Rewards cannot be claimed before a payout address is registered
+        let info = message_info(&addr("payout"), &[]);
+        let msg = ExecuteMsg::ClaimWitnessRewards {
+            witness: witness.clone(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone());
+        assert_eq!(res, Err(ContractError::PayoutNotRegistered {}));
+
+        // Signatures over another payout address are rejected
+        let message = payout_registration_message(&env.contract.address, &addr(USER));
+        let register = ExecuteMsg::RegisterWitnessPayout {
+            witness: witness.clone(),
+            signature: eth_sign(&key, &message),
+            domain: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), register);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let message = payout_registration_message(&env.contract.address, &addr("payout"));
+        let register = ExecuteMsg::RegisterWitnessPayout {
+            witness: witness.to_uppercase().replace("0X", "0x"),
+            signature: eth_sign(&key, &message),
+            domain: None,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), register).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr(USER), &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: addr("payout").to_string(),
+                amount: vec![coin(40, "uxion")],
+            })
+        );
+
+        let res = execute(deps.as_mut(), env, info, msg);
+        assert_eq!(res, Err(ContractError::NothingToWithdraw {}));
+    }
+
+    #[test]
+    fn guardian_pauses_verification() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+        EPOCHS
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
+            .unwrap();
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::UpdateGuardian {
+            guardian: Some(addr("guardian").to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let guardian = message_info(&addr("guardian"), &[]);
+        let msg = ExecuteMsg::Pause {
+            scope: PauseScope::Verification,
+        };
+        execute(deps.as_mut(), env.clone(), guardian.clone(), msg).unwrap();
+
+        let state = query_pause_state(deps.as_ref()).unwrap();
+        assert!(state.verification);
+        assert!(!state.admin);
+
+        let user = message_info(&addr(USER), &[]);
+        let res = verify_proof(deps.as_mut(), create_proof_msg(), env.clone(), user.clone());
+        assert_eq!(res, Err(ContractError::Paused {}));
+
+        // Admin actions are still allowed
+        let msg = ExecuteMsg::UpdateBadgeConfig { badge: None };
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr(OWNER), &[]),
+            msg,
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Unpause {
+            scope: PauseScope::Verification,
+        };
+        execute(deps.as_mut(), env.clone(), guardian, msg).unwrap();
+        verify_proof(deps.as_mut(), create_proof_msg(), env, user).unwrap();
+    }
+
+    #[test]
+    fn owner_pauses_admin_actions() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+        EPOCHS
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
+            .unwrap();
+
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::Pause {
+            scope: PauseScope::Admin,
+        };
+        execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+
+        let msg = ExecuteMsg::AddEpoch {
+            witness: vec![],
+            minimum_witness: Uint128::zero(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), msg);
+        assert_eq!(res, Err(ContractError::Paused {}));
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            info,
+            ExecuteMsg::WithdrawFees {},
+        );
+        assert_eq!(res, Err(ContractError::Paused {}));
+
+        // Verification keeps working
+        let user = message_info(&addr(USER), &[]);
+        verify_proof(deps.as_mut(), create_proof_msg(), env, user).unwrap();
+    }
+
+    #[test]
+    fn pause_unauthorized() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let info = message_info(&addr(USER), &[]);
+        let msg = ExecuteMsg::Pause {
+            scope: PauseScope::All,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn epoch_manager_role_rotates_epochs() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let owner = message_info(&addr(OWNER), &[]);
+        let bot = message_info(&addr("bot"), &[]);
+        let add_epoch_msg = ExecuteMsg::AddEpoch {
+            witness: vec![],
+            minimum_witness: Uint128::zero(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            bot.clone(),
+            add_epoch_msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::EpochManager,
+            address: addr("bot").to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let members = query_role_members(deps.as_ref(), Role::EpochManager).unwrap();
+        assert_eq!(members.members, vec![addr("bot")]);
+
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            bot.clone(),
+            add_epoch_msg.clone(),
+        )
+        .unwrap();
+        assert!(EPOCHS.has(&deps.storage, 1));
+
+        // Other admin paths need their own role
+        let msg = ExecuteMsg::WithdrawFees {};
+        let res = execute(deps.as_mut(), env.clone(), bot.clone(), msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::FeeManager,
+            address: addr("bot").to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), bot.clone(), msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::EpochManager,
+            address: addr("bot").to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+        let res = execute(deps.as_mut(), env, bot, add_epoch_msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn grant_owner_role_transfers_ownership() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let owner = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::RevokeRole {
+            role: Role::Owner,
+            address: addr(OWNER).to_string(),
+        };
+        let res = execute(deps.as_mut(), env.clone(), owner.clone(), msg);
+        assert_eq!(res, Err(ContractError::CannotRevokeOwner {}));
+
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::Owner,
+            address: addr("new_owner").to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let members = query_role_members(deps.as_ref(), Role::Owner).unwrap();
+        assert_eq!(members.members, vec![addr("new_owner")]);
+
+        let msg = ExecuteMsg::Pause {
+            scope: PauseScope::All,
+        };
+        let res = execute(deps.as_mut(), env, owner, msg);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+    }
+
+    #[test]
+    fn timelocked_epoch_addition() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let owner = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::SetEpochDelay { seconds: 3600 };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+        let witness = vec![Witness {
+            address: RECLAIM_ADDRESS.to_string(),
+            host: "https://w.com".to_string(),
+            scheme: SignatureScheme::EthPersonalSign,
+            public_key: None,
+        }];
+        let msg = ExecuteMsg::AddEpoch {
+            witness: witness.clone(),
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+
+        // Nothing is active yet, the change waits in the queue
+        assert!(!EPOCHS.has(&deps.storage, 1));
+        let pending = query_pending_epoch_changes(deps.as_ref()).unwrap();
+        assert_eq!(pending.delay, 3600);
+        assert_eq!(
+            pending.changes,
+            vec![PendingEpochChange {
+                id: 1,
+                domain: None,
+                change: EpochChange::AddEpoch {
+                    witness: witness.clone(),
+                    minimum_witness: Uint128::one(),
+                    policy: EpochPolicy::default(),
+                },
+                proposer: addr(OWNER),
+                executable_after: env.block.time.plus_seconds(3600).nanos(),
+            }]
+        );
+
+        let anyone = message_info(&addr(USER), &[]);
+        let msg = ExecuteMsg::ExecuteEpochChange { id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), anyone.clone(), msg.clone());
+        assert_eq!(
+            res,
+            Err(ContractError::TimelockNotExpired {
+                executable_after: env.block.time.plus_seconds(3600).nanos(),
+            })
+        );
+
+        env.block.time = env.block.time.plus_seconds(3600);
+        execute(deps.as_mut(), env.clone(), anyone.clone(), msg.clone()).unwrap();
+        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
+        assert_eq!(epoch.witness, witness);
+        assert_eq!(epoch.timestamp_start, env.block.time.nanos());
+
+        // A change can only be executed once
+        let res = execute(deps.as_mut(), env, anyone, msg);
+        assert_eq!(res, Err(ContractError::PendingChangeNotFound { id: 1 }));
+    }
+
+    #[test]
+    fn guardian_cancels_pending_epoch_change() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let owner = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::UpdateGuardian {
+            guardian: Some(addr("guardian").to_string()),
+        };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetEpochDelay { seconds: 60 };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::AddEpoch {
+            witness: vec![],
+            minimum_witness: Uint128::zero(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+
+        let msg = ExecuteMsg::CancelEpochChange { id: 1 };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr(USER), &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let guardian = message_info(&addr("guardian"), &[]);
+        execute(deps.as_mut(), env.clone(), guardian.clone(), msg.clone()).unwrap();
+        assert!(query_pending_epoch_changes(deps.as_ref())
+            .unwrap()
+            .changes
+            .is_empty());
+
+        let res = execute(deps.as_mut(), env, guardian, msg);
+        assert_eq!(res, Err(ContractError::PendingChangeNotFound { id: 1 }));
+    }
+
+    #[test]
+    fn shortening_epoch_delay_is_timelocked() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let owner = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::SetEpochDelay { seconds: 600 };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetEpochDelay { seconds: 0 };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().epoch_delay, 600);
+
+        env.block.time = env.block.time.plus_seconds(600);
+        let msg = ExecuteMsg::ExecuteEpochChange { id: 1 };
+        execute(deps.as_mut(), env, owner, msg).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap().epoch_delay, 0);
+    }
+
+    #[test]
+    fn queued_witness_update_keeps_its_epoch() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let owner = message_info(&addr(OWNER), &[]);
+        let first = testing::test_witnesses(2);
+        let msg = ExecuteMsg::AddEpoch {
+            witness: first.iter().map(testing::TestWitness::witness).collect(),
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::SetEpochDelay { seconds: 60 };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+        // The update is queued while epoch 1 is current, epoch 2 is added before it runs
+        let rotated = vec![testing::TestWitness::new(20).witness()];
+        let msg = ExecuteMsg::UpdateWitnesses {
+            witness: rotated.clone(),
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let pending = query_pending_epoch_changes(deps.as_ref()).unwrap();
+        assert!(matches!(
+            pending.changes[0].change,
+            EpochChange::UpdateWitnesses { epoch_id, .. } if epoch_id == Uint128::one()
+        ));
+        let second = vec![testing::TestWitness::new(30).witness()];
+        let msg = ExecuteMsg::AddEpoch {
+            witness: second.clone(),
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+        env.block.time = env.block.time.plus_seconds(60);
+        let msg = ExecuteMsg::ExecuteEpochChange { id: 2 };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::ExecuteEpochChange { id: 1 };
+        let res = execute(deps.as_mut(), env, owner, msg).unwrap();
+        assert_eq!(res.attributes[1].value, "1");
+
+        assert_eq!(EPOCHS.load(&deps.storage, 1).unwrap().witness, rotated);
+        assert_eq!(EPOCHS.load(&deps.storage, 2).unwrap().witness, second);
+    }
+
+    #[test]
+    fn witness_manager_updates_current_epoch() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let owner = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::GrantRole {
+            role: Role::WitnessManager,
+            address: addr("ops").to_string(),
+        };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+
+        let ops = message_info(&addr("ops"), &[]);
+        let witness = vec![Witness {
+            address: RECLAIM_ADDRESS.to_string(),
+            host: "https://w.com".to_string(),
+            scheme: SignatureScheme::EthPersonalSign,
+            public_key: None,
+        }];
+        let msg = ExecuteMsg::UpdateWitnesses {
+            witness: witness.clone(),
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), ops.clone(), msg.clone());
+        assert_eq!(res, Err(ContractError::EpochNotFound { id: 0 }));
+
+        let add = ExecuteMsg::AddEpoch {
+            witness: vec![],
+            minimum_witness: Uint128::zero(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        execute(deps.as_mut(), env.clone(), owner, add.clone()).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), ops.clone(), add);
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        execute(deps.as_mut(), env, ops, msg).unwrap();
+        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
+        assert_eq!(epoch.witness, witness);
+        assert_eq!(epoch.minimum_witness_for_claim_creation, Uint128::one());
+    }
+
+    #[test]
+    fn sudo_recovers_contract_without_admin_keys() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        // Admin is frozen and epoch changes are timelocked
+        let owner = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::SetEpochDelay { seconds: 3600 };
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg).unwrap();
+        let msg = ExecuteMsg::Pause {
+            scope: PauseScope::Admin,
+        };
+        execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+
+        let msg = SudoMsg::AddEpoch {
+            witness: create_test_epoch().witness,
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(
+            EPOCHS.load(&deps.storage, 1).unwrap().witness,
+            create_test_epoch().witness
+        );
+
+        let msg = SudoMsg::UpdateOwner {
+            owner: addr("new_owner").to_string(),
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+
+        let msg = SudoMsg::UpdateConfig {
+            guardian: Some(Some(addr("guardian").to_string())),
+            epoch_delay: Some(0),
+            fee: Some(Some(coin(100, "uxion"))),
+            treasury: None,
+            witness_share: None,
+            badge: None,
+            limits: None,
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        let msg = SudoMsg::Unpause {
+            scope: PauseScope::Admin,
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, addr("new_owner"));
+        assert_eq!(config.guardian, Some(addr("guardian")));
+        assert_eq!(config.fee, Some(coin(100, "uxion")));
+        assert_eq!(config.epoch_delay, 0);
+        assert!(!config.paused.admin);
+
+        // null clears an optional field, leaving it out keeps it
+        let msg: SudoMsg =
+            cosmwasm_std::from_json(r#"{"update_config":{"fee":null,"epoch_delay":60}}"#).unwrap();
+        sudo(deps.as_mut(), env, msg).unwrap();
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.fee, None);
+        assert_eq!(config.guardian, Some(addr("guardian")));
+        assert_eq!(config.epoch_delay, 60);
+    }
+
+    #[test]
+    fn sudo_ends_epoch_and_pauses_verification() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+        EPOCHS
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
+            .unwrap();
+
+        let user = message_info(&addr(USER), &[]);
+        let msg = SudoMsg::Pause {
+            scope: PauseScope::Verification,
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        let res = verify_proof(deps.as_mut(), create_proof_msg(), env.clone(), user.clone());
+        assert_eq!(res, Err(ContractError::Paused {}));
+
+        let msg = SudoMsg::Unpause {
+            scope: PauseScope::Verification,
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        let msg = SudoMsg::EndEpoch {
+            id: 1,
+            domain: None,
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+
+        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
+        assert!(epoch.ended);
+        assert_eq!(epoch.timestamp_end, env.block.time.nanos());
+        let res = verify_proof(deps.as_mut(), create_proof_msg(), env.clone(), user);
+        assert_eq!(res, Err(ContractError::EpochEnded {}));
+
+        let msg = SudoMsg::EndEpoch {
+            id: 2,
+            domain: None,
+        };
+        let res = sudo(deps.as_mut(), env, msg);
+        assert_eq!(res, Err(ContractError::EpochNotFound { id: 2 }));
+    }
+
+    #[test]
+    fn sudo_manages_domain_epochs() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+        let owner = message_info(&addr(OWNER), &[]);
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            owner,
+            create_domain_msg(DomainPolicy::default()),
+        )
+        .unwrap();
+
+        let msg = SudoMsg::AddEpoch {
+            witness: create_test_epoch().witness,
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: Some("partner".to_string()),
+        };
+        let res = sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        assert_eq!(res.attributes[2].value, "partner");
+        assert!(!EPOCHS.has(&deps.storage, 1));
+
+        let msg = SudoMsg::EndEpoch {
+            id: 1,
+            domain: Some("partner".to_string()),
+        };
+        sudo(deps.as_mut(), env.clone(), msg).unwrap();
+        let epoch = load_epoch(&deps.storage, Some("partner"), 1).unwrap();
+        assert!(epoch.ended);
+
+        let msg = SudoMsg::EndEpoch {
+            id: 1,
+            domain: Some("unknown".to_string()),
+        };
+        let res = sudo(deps.as_mut(), env, msg);
+        assert_eq!(
+            res,
+            Err(ContractError::DomainNotFound {
+                name: "unknown".to_string()
+            })
+        );
+    }
+
+    fn create_domain_msg(policy: DomainPolicy) -> ExecuteMsg {
+        ExecuteMsg::CreateDomain {
+            name: "partner".to_string(),
+            admin: addr("partner_admin").to_string(),
+            policy,
+        }
+    }
+
+    fn add_domain_epoch_msg() -> ExecuteMsg {
+        ExecuteMsg::AddEpoch {
+            witness: create_test_epoch().witness,
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: Some("partner".to_string()),
+        }
+    }
+
+    #[test]
+    fn domain_admin_manages_own_epochs() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let owner = message_info(&addr(OWNER), &[]);
+        let partner = message_info(&addr("partner_admin"), &[]);
+
+        // Only the owner creates domains
+        let msg = create_domain_msg(DomainPolicy::default());
+        let res = execute(deps.as_mut(), env.clone(), partner.clone(), msg.clone());
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), owner.clone(), msg.clone()).unwrap();
+        let res = execute(deps.as_mut(), env.clone(), owner.clone(), msg);
+        assert_eq!(res, Err(ContractError::DomainExists {}));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            owner.clone(),
+            ExecuteMsg::CreateDomain {
+                name: "Not Valid".to_string(),
+                admin: addr("partner_admin").to_string(),
+                policy: DomainPolicy::default(),
+            },
+        );
+        assert_eq!(res, Err(ContractError::InvalidDomainName {}));
+
+        // The domain admin runs its own epoch sequence, but not the default one
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            partner.clone(),
+            add_domain_epoch_msg(),
+        )
+        .unwrap();
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            partner.clone(),
+            ExecuteMsg::AddEpoch {
+                witness: create_test_epoch().witness,
+                minimum_witness: Uint128::one(),
+                policy: EpochPolicy::default(),
+                domain: None,
+            },
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+
+        let domain = query_domain(deps.as_ref(), "partner".to_string()).unwrap();
+        assert_eq!(domain.domain.current_epoch, Uint128::one());
+        assert_eq!(
+            CONFIG.load(&deps.storage).unwrap().current_epoch,
+            Uint128::zero()
+        );
+        let ids = query_all_epoch_ids(deps.as_ref(), Some("partner".to_string())).unwrap();
+        assert_eq!(ids.ids, vec![1]);
+        assert!(query_all_epoch_ids(deps.as_ref(), None)
+            .unwrap()
+            .ids
+            .is_empty());
+
+        // Proofs verify against the domain's epochs only
+        let mut proof = create_proof_msg();
+        proof.domain = Some("partner".to_string());
+        verify_proof(
+            deps.as_mut(),
+            proof,
+            env.clone(),
+            message_info(&addr(USER), &[]),
+        )
+        .unwrap();
+        let res = verify_proof(
+            deps.as_mut(),
+            create_proof_msg(),
+            env,
+            message_info(&addr(USER), &[]),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn domain_policy_restricts_providers() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let policy = DomainPolicy {
+            allowed_providers: vec!["0xother".to_string()],
+        };
+        let owner = message_info(&addr(OWNER), &[]);
+        let partner = message_info(&addr("partner_admin"), &[]);
+        execute(deps.as_mut(), env.clone(), owner, create_domain_msg(policy)).unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            partner.clone(),
+            add_domain_epoch_msg(),
+        )
+        .unwrap();
+
+        let mut proof = create_proof_msg();
+        proof.domain = Some("partner".to_string());
+        let res = verify_proof(
+            deps.as_mut(),
+            proof.clone(),
+            env.clone(),
+            message_info(&addr(USER), &[]),
+        );
+        assert_eq!(
+            res,
+            Err(ContractError::ProviderNotAllowed {
+                provider_hash: PROVIDER_HASH.to_string(),
+            })
+        );
+
+        // The domain admin widens its policy
+        let msg = ExecuteMsg::UpdateDomain {
+            name: "partner".to_string(),
+            admin: None,
+            policy: Some(DomainPolicy {
+                allowed_providers: vec![PROVIDER_HASH.to_string()],
+            }),
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr(USER), &[]),
+            msg.clone(),
+        );
+        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        execute(deps.as_mut(), env.clone(), partner, msg).unwrap();
+
+        verify_proof(deps.as_mut(), proof, env, message_info(&addr(USER), &[])).unwrap();
+    }
+
+    // A fresh claim in epoch 1, signed by `sign` over its serialised form
+    fn signed_proof_msg(sign: impl Fn(&str) -> String) -> ProofMsg {
+        let claim_info = ClaimInfo {
+            provider: "http".to_string(),
+            parameters: "{}".to_string(),
+            context: format!(r#"{{"providerHash":"{}"}}"#, PROVIDER_HASH),
+        };
+        let claim = CompleteClaimData {
+            identifier: claim_info.hash(),
+            owner: ZERO_ADDRESS.to_string(),
+            epoch: 1,
+            timestampS: 1748539856,
+        };
+        let signature = sign(&claim.serialise());
+        ProofMsg {
+            proof: Proof {
+                claimInfo: claim_info,
+                signedClaim: SignedClaim {
+                    claim,
+                    signatures: vec![signature],
+                },
+            },
+            domain: None,
+            owner_signature: None,
+        }
+    }
+
+    fn save_single_witness_epoch(deps: DepsMut, witness: Witness) {
+        let epoch = Epoch {
+            witness: vec![witness],
+            ..create_test_epoch()
+        };
+        EPOCHS.save(deps.storage, 1, &epoch).unwrap();
+    }
+
+    // An ed25519 witness going by the address of its key, written in upper case
+    fn ed25519_witness(seed: u8) -> (ed25519_zebra::SigningKey, Witness) {
+        let key = ed25519_zebra::SigningKey::from([seed; 32]);
+        let mut witness = Witness {
+            address: String::new(),
+            host: "https://w.com".to_string(),
+            scheme: SignatureScheme::Ed25519,
+            public_key: Some(hex::encode(
+                ed25519_zebra::VerificationKey::from(&key).as_ref(),
+            )),
+        };
+        witness.address = witness
+            .key_address()
+            .unwrap()
+            .to_uppercase()
+            .replace("0X", "0x");
+        (key, witness)
+    }
+
+    fn add_epoch_msg(witness: Witness, domain: Option<&str>) -> ExecuteMsg {
+        ExecuteMsg::AddEpoch {
+            witness: vec![witness],
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: domain.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn ed25519_witness_registers_payout() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+
+        let (key, witness) = ed25519_witness(3);
+        let owner = message_info(&addr(OWNER), &[]);
+        let msg = add_epoch_msg(witness.clone(), None);
+        execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+        let address = witness.address.to_lowercase();
+        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
+        assert_eq!(epoch.witness[0].address, address);
+
+        let message = payout_registration_message(&env.contract.address, &addr("payout"));
+        let info = message_info(&addr("payout"), &[]);
+
+        // Only the key of the epoch record can register
+        let other = ed25519_zebra::SigningKey::from([4u8; 32]);
+        let register = ExecuteMsg::RegisterWitnessPayout {
+            witness: witness.address.clone(),
+            signature: hex::encode(other.sign(message.as_bytes()).to_bytes()),
+            domain: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), register);
+        assert_eq!(res, Err(ContractError::SignatureErr {}));
+
+        let register = ExecuteMsg::RegisterWitnessPayout {
+            witness: witness.address.clone(),
+            signature: hex::encode(key.sign(message.as_bytes()).to_bytes()),
+            domain: None,
+        };
+        execute(deps.as_mut(), env.clone(), info, register).unwrap();
+        let rewards = query_witness_rewards(deps.as_ref(), witness.address.clone()).unwrap();
+        assert_eq!(rewards.payout, Some(addr("payout")));
+
+        // The registered payout can't be replaced, even with a valid signature
+        let message = payout_registration_message(&env.contract.address, &addr(USER));
+        let register = ExecuteMsg::RegisterWitnessPayout {
+            witness: address,
+            signature: hex::encode(key.sign(message.as_bytes()).to_bytes()),
+            domain: None,
+        };
+        let res = execute(deps.as_mut(), env, message_info(&addr(USER), &[]), register);
+        assert_eq!(res, Err(ContractError::PayoutAlreadyRegistered {}));
+    }
+
+    #[test]
+    fn domain_admin_cannot_take_witness_address() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        setup_contract(deps.as_mut());
+        let owner = message_info(&addr(OWNER), &[]);
+        let partner = message_info(&addr("partner_admin"), &[]);
+        let msg = create_domain_msg(DomainPolicy::default());
+        execute(deps.as_mut(), env.clone(), owner, msg).unwrap();
+
+        // A keyed witness can't go by the address of an eth witness or of another key
+        let victim = eth_address(&SigningKey::from_slice(&[7u8; 32]).unwrap());
+        let (key, mut witness) = ed25519_witness(3);
+        for address in [victim.clone(), ed25519_witness(4).1.address] {
+            witness.address = address;
+            let msg = add_epoch_msg(witness.clone(), Some("partner"));
+            let res = execute(deps.as_mut(), env.clone(), partner.clone(), msg);
+            assert!(matches!(res, Err(ContractError::InvalidWitnessKey { .. })));
+        }
+
+        // A record in the domain only counts when registering for that domain
+        let (_, witness) = ed25519_witness(3);
+        let msg = add_epoch_msg(witness.clone(), Some("partner"));
+        execute(deps.as_mut(), env.clone(), partner.clone(), msg).unwrap();
+        let message = payout_registration_message(&env.contract.address, &addr("payout"));
+        let signature = hex::encode(key.sign(message.as_bytes()).to_bytes());
+        let info = message_info(&addr("payout"), &[]);
+        let register = ExecuteMsg::RegisterWitnessPayout {
+            witness: witness.address.clone(),
+            signature: signature.clone(),
+            domain: None,
+        };
+        let res = execute(deps.as_mut(), env.clone(), info.clone(), register);
+        assert!(res.is_err());
+        let register = ExecuteMsg::RegisterWitnessPayout {
+            witness: witness.address.clone(),
+            signature,
+            domain: Some("partner".to_string()),
+        };
+        execute(deps.as_mut(), env, info, register).unwrap();
+        let rewards = query_witness_rewards(deps.as_ref(), witness.address).unwrap();
+        assert_eq!(rewards.payout, Some(addr("payout")));
+    }
+
+    #[test]
+    fn verify_proof_from_ed25519_witness() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let key = ed25519_zebra::SigningKey::from([3u8; 32]);
+        let public_key = ed25519_zebra::VerificationKey::from(&key);
+        save_single_witness_epoch(
+            deps.as_mut(),
             Witness {
-                address: RECLAIM_ADDRESS.to_string(),
-                host: "https://w2.com".to_string(),
+                address: "ed25519-witness".to_string(),
+                host: "https://w.com".to_string(),
+                scheme: SignatureScheme::Ed25519,
+                public_key: Some(hex::encode(public_key.as_ref())),
             },
-        ];
+        );
+
+        let proof = signed_proof_msg(|message| {
+            append_0x(&hex::encode(key.sign(message.as_bytes()).to_bytes()))
+        });
+        let res = verify_proof(
+            deps.as_mut(),
+            proof,
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        )
+        .unwrap();
+        let signer = res.events.iter().find(|e| e.ty == "signer").unwrap();
+        assert_eq!(signer.attributes[0].value, "ed25519-witness");
+
+        // A signature by another key does not verify
+        let other = ed25519_zebra::SigningKey::from([4u8; 32]);
+        let proof = signed_proof_msg(|message| {
+            append_0x(&hex::encode(other.sign(message.as_bytes()).to_bytes()))
+        });
+        let res = verify_proof(
+            deps.as_mut(),
+            proof,
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        );
+        assert_eq!(res, Err(ContractError::InvalidSignature { index: 0 }));
+    }
+
+    #[test]
+    fn verify_proof_from_raw_secp256k1_witness() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        let key = SigningKey::from_slice(&[9u8; 32]).unwrap();
+        let public_key = key.verifying_key().to_encoded_point(true);
+        save_single_witness_epoch(
+            deps.as_mut(),
+            Witness {
+                address: "secp256k1-witness".to_string(),
+                host: "https://w.com".to_string(),
+                scheme: SignatureScheme::Secp256k1,
+                public_key: Some(hex::encode(public_key.as_bytes())),
+            },
+        );
+
+        let proof = signed_proof_msg(|message| {
+            let hash = Sha256::digest(message.as_bytes());
+            let (signature, _) = key.sign_prehash_recoverable(&hash).unwrap();
+            append_0x(&hex::encode(signature.to_bytes()))
+        });
+        let res = verify_proof(
+            deps.as_mut(),
+            proof,
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        )
+        .unwrap();
+        let signer = res.events.iter().find(|e| e.ty == "signer").unwrap();
+        assert_eq!(signer.attributes[0].value, "secp256k1-witness");
+    }
+
+    #[test]
+    fn add_epoch_requires_public_key_for_keyed_schemes() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
 
-        let info = mock_info(OWNER, &[]);
         let msg = ExecuteMsg::AddEpoch {
-            witness: witnesses.clone(),
-            minimum_witness: Uint128::from(2u128),
+            witness: vec![Witness {
+                address: "ed25519-witness".to_string(),
+                host: "https://w.com".to_string(),
+                scheme: SignatureScheme::Ed25519,
+                public_key: Some("0x1234".to_string()),
+            }],
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
         };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            message_info(&addr(OWNER), &[]),
+            msg,
+        );
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidWitnessKey {
+                address: "ed25519-witness".to_string(),
+            })
+        );
+    }
 
-        // First epoch
-        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    // Five eth witnesses, three selected per claim of which `policy` decides how many must sign
+    fn save_threshold_epoch(deps: DepsMut, policy: EpochPolicy) -> (Epoch, Vec<SigningKey>) {
+        let keys: Vec<SigningKey> = (1..=5u8)
+            .map(|i| SigningKey::from_slice(&[i; 32]).unwrap())
+            .collect();
+        let epoch = Epoch {
+            witness: keys
+                .iter()
+                .map(|key| Witness {
+                    address: eth_address(key),
+                    host: "https://w.com".to_string(),
+                    scheme: SignatureScheme::EthPersonalSign,
+                    public_key: None,
+                })
+                .collect(),
+            minimum_witness_for_claim_creation: Uint128::new(3),
+            policy,
+            ..create_test_epoch()
+        };
+        EPOCHS.save(deps.storage, 1, &epoch).unwrap();
+        (epoch, keys)
+    }
 
-        // Verify state changes
-        let config = CONFIG.load(&deps.storage).unwrap();
-        assert_eq!(config.current_epoch, Uint128::one());
+    // The keys of the distinct witnesses selected for the proof
+    fn selected_keys(epoch: &Epoch, keys: &[SigningKey], proof: &ProofMsg) -> Vec<SigningKey> {
+        let selected = Witness::get_addresses(fetch_witness_for_claim(
+            epoch.clone(),
+            proof.proof.signedClaim.claim.identifier.clone(),
+            mock_env().block.time,
+        ));
+        keys.iter()
+            .filter(|key| selected.contains(&eth_address(key)))
+            .cloned()
+            .collect()
+    }
 
-        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
-        assert_eq!(epoch.id, Uint128::one());
-        assert_eq!(epoch.witness, witnesses);
+    #[test]
+    fn threshold_of_selected_witnesses_is_enough() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let policy = EpochPolicy {
+            threshold: Some(Uint128::new(2)),
+            unknown_signers: UnknownSignerPolicy::Ignore,
+        };
+        let (epoch, keys) = save_threshold_epoch(deps.as_mut(), policy);
+
+        let mut proof = signed_proof_msg(|_| String::new());
+        let selected = selected_keys(&epoch, &keys, &proof);
+        assert!(selected.len() >= 2);
+        let message = proof.proof.signedClaim.claim.serialise();
+        let unknown = SigningKey::from_slice(&[99u8; 32]).unwrap();
+
+        // One signature short of the threshold, unknown signers don't count
+        proof.proof.signedClaim.signatures = vec![
+            eth_sign(&selected[0], &message),
+            eth_sign(&unknown, &message),
+        ];
+        let res = verify_proof(
+            deps.as_mut(),
+            proof.clone(),
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        );
         assert_eq!(
-            epoch.minimum_witness_for_claim_creation,
-            Uint128::from(2u128)
+            res,
+            Err(ContractError::WitnessMismatchErr {
+                required: 2,
+                signed: 1
+            })
         );
-        assert_eq!(epoch.timestamp_start, env.block.time.nanos());
-        assert_eq!(epoch.timestamp_end, env.block.time.plus_days(1).nanos());
+
+        proof
+            .proof
+            .signedClaim
+            .signatures
+            .push(eth_sign(&selected[1], &message));
+        let res = verify_proof(
+            deps.as_mut(),
+            proof,
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        )
+        .unwrap();
+        let signers: Vec<_> = res.events.iter().filter(|e| e.ty == "signer").collect();
+        assert_eq!(signers.len(), 2);
+    }
+
+    #[test]
+    fn threshold_counts_a_witness_drawn_twice_once() {
+        let witness = testing::TestWitness::new(1).witness();
+        let policy = EpochPolicy {
+            threshold: Some(Uint128::new(2)),
+            unknown_signers: UnknownSignerPolicy::Reject,
+        };
+
+        // Both draws picked the same witness, its one signature is all there can be
+        let selected = vec![witness.clone(), witness.clone()];
+        let signed = vec![witness.address.clone()];
+        let signers = check_signers(&signed, &selected, &policy).unwrap();
+        assert_eq!(signers, signed);
+
+        // Nothing signed is never enough
+        let res = check_signers(&[], &selected, &policy);
+        assert_eq!(
+            res,
+            Err(ContractError::WitnessMismatchErr {
+                required: 1,
+                signed: 0
+            })
+        );
+    }
+
+    #[test]
+    fn unknown_signers_rejected_by_default() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let policy = EpochPolicy {
+            threshold: Some(Uint128::one()),
+            unknown_signers: UnknownSignerPolicy::Reject,
+        };
+        let (epoch, keys) = save_threshold_epoch(deps.as_mut(), policy);
+
+        let mut proof = signed_proof_msg(|_| String::new());
+        let selected = selected_keys(&epoch, &keys, &proof);
+        let message = proof.proof.signedClaim.claim.serialise();
+        let unknown = SigningKey::from_slice(&[99u8; 32]).unwrap();
+        proof.proof.signedClaim.signatures = vec![
+            eth_sign(&selected[0], &message),
+            eth_sign(&unknown, &message),
+        ];
+        let res = verify_proof(
+            deps.as_mut(),
+            proof,
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        );
+        assert_eq!(
+            res,
+            Err(ContractError::UnexpectedSigner {
+                address: eth_address(&unknown),
+                index: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn add_epoch_with_invalid_threshold() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+
+        for threshold in [0u128, 2] {
+            let msg = ExecuteMsg::AddEpoch {
+                witness: create_test_epoch().witness,
+                minimum_witness: Uint128::one(),
+                policy: EpochPolicy {
+                    threshold: Some(Uint128::new(threshold)),
+                    unknown_signers: UnknownSignerPolicy::Reject,
+                },
+                domain: None,
+            };
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                message_info(&addr(OWNER), &[]),
+                msg,
+            );
+            assert_eq!(res, Err(ContractError::InvalidThreshold {}));
+        }
     }
 
     #[test]
-    fn add_epoch_unauthorized() {
+    fn verify_proof_rejects_duplicate_signer() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
 
-        let info = mock_info(USER, &[]); // Non-owner
-        let msg = ExecuteMsg::AddEpoch {
-            witness: vec![],
-            minimum_witness: Uint128::from(1u128),
+        // Two signatures from the single selected witness used to satisfy a minimum of two
+        let mut proof = create_proof_msg();
+        let signature = proof.proof.signedClaim.signatures[0].clone();
+        proof.proof.signedClaim.signatures.push(signature);
+        let epoch = Epoch {
+            minimum_witness_for_claim_creation: Uint128::new(2),
+            ..create_test_epoch()
         };
+        EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
 
-        let res = execute(deps.as_mut(), mock_env(), info, msg);
-        assert_eq!(res, Err(ContractError::Unauthorized {}));
+        let res = verify_proof(
+            deps.as_mut(),
+            proof,
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        );
+        assert_eq!(
+            res,
+            Err(ContractError::DuplicateSigner {
+                address: RECLAIM_ADDRESS.to_string(),
+            })
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(32))]
+
+        // No ordering or repetition of valid signatures stands in for a missing witness
+        #[test]
+        fn repeated_signatures_never_satisfy_minimum(
+            picks in prop::collection::vec(0usize..8, 1..8),
+        ) {
+            let mut deps = mock_dependencies();
+            setup_contract(deps.as_mut());
+            let (epoch, keys) = save_threshold_epoch(deps.as_mut(), EpochPolicy::default());
+
+            let mut proof = signed_proof_msg(|_| String::new());
+            let selected = selected_keys(&epoch, &keys, &proof);
+            let message = proof.proof.signedClaim.claim.serialise();
+            let picks: Vec<usize> = picks.iter().map(|pick| pick % selected.len()).collect();
+            proof.proof.signedClaim.signatures = picks
+                .iter()
+                .map(|pick| eth_sign(&selected[*pick], &message))
+                .collect();
+            let mut distinct = picks.clone();
+            distinct.sort();
+            distinct.dedup();
+
+            let res = verify_proof(
+                deps.as_mut(),
+                proof,
+                mock_env(),
+                message_info(&addr(USER), &[]),
+            );
+            if distinct.len() < picks.len() {
+                let is_duplicate = matches!(res, Err(ContractError::DuplicateSigner { .. }));
+                prop_assert!(is_duplicate);
+            } else if distinct.len() < selected.len() {
+                prop_assert_eq!(res, Err(ContractError::WitnessMismatchErr {
+                    required: selected.len(),
+                    signed: distinct.len(),
+                }));
+            } else {
+                prop_assert!(res.is_ok());
+            }
+        }
     }
 
     #[test]
-    fn epoch_id_increments_correctly() {
+    fn verify_proof_reports_missing_epoch_and_domain() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
-        let env = mock_env();
 
-        let info = mock_info(OWNER, &[]);
-        let msg = ExecuteMsg::AddEpoch {
-            witness: vec![Witness {
+        let res = verify_proof(
+            deps.as_mut(),
+            create_proof_msg(),
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        );
+        assert_eq!(res, Err(ContractError::EpochNotFound { id: 1 }));
+
+        let mut proof = create_proof_msg();
+        proof.domain = Some("missing".to_string());
+        let res = verify_proof(
+            deps.as_mut(),
+            proof,
+            mock_env(),
+            message_info(&addr(USER), &[]),
+        );
+        assert_eq!(
+            res,
+            Err(ContractError::DomainNotFound {
+                name: "missing".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn error_codes_are_stable_and_published() {
+        let errors = vec![
+            ContractError::Std(StdError::generic_err("std")),
+            ContractError::Unauthorized {},
+            ContractError::AlreadyExists {},
+            ContractError::PubKeyErr {},
+            ContractError::SignatureErr {},
+            ContractError::HashMismatchErr {
+                expected: "0x1".to_string(),
+                computed: "0x2".to_string(),
+            },
+            ContractError::WitnessMismatchErr {
+                required: 2,
+                signed: 1,
+            },
+            ContractError::InvalidSignature { index: 0 },
+            ContractError::UnexpectedSigner {
                 address: ZERO_ADDRESS.to_string(),
-                host: "https://w.com".to_string(),
-            }],
-            minimum_witness: Uint128::one(),
-        };
+                index: 0,
+            },
+            ContractError::EpochNotFound { id: 1 },
+            ContractError::DomainNotFound {
+                name: "partner".to_string(),
+            },
+            ContractError::PendingChangeNotFound { id: 1 },
+            ContractError::InsufficientFunds {
+                required: coin(2, "uxion"),
+                sent: coin(1, "uxion"),
+            },
+            ContractError::WrongDenom {
+                expected: "uxion".to_string(),
+                received: "uatom".to_string(),
+            },
+            ContractError::NothingToWithdraw {},
+            ContractError::InvalidFeeShare {},
+            ContractError::PayoutNotRegistered {},
+            ContractError::Paused {},
+            ContractError::CannotRevokeOwner {},
+            ContractError::TimelockNotExpired {
+                executable_after: 1,
+            },
+            ContractError::EpochEnded {},
+            ContractError::InvalidDomainName {},
+            ContractError::InvalidWitnessKey {
+                address: ZERO_ADDRESS.to_string(),
+            },
+            ContractError::DuplicateSigner {
+                address: ZERO_ADDRESS.to_string(),
+            },
+            ContractError::InvalidThreshold {},
+            ContractError::DomainExists {},
+            ContractError::ProviderNotAllowed {
+                provider_hash: PROVIDER_HASH.to_string(),
+            },
+            ContractError::MalformedProof {
+                reason: "missing claimData".to_string(),
+            },
+            ContractError::InputTooLarge {
+                field: "parameters".to_string(),
+                size: 2,
+                max: 1,
+            },
+            ContractError::TooManyItems {
+                field: "signatures".to_string(),
+                count: 2,
+                max: 1,
+            },
+            ContractError::IbcPeerNotAllowed {
+                connection_id: "connection-0".to_string(),
+                port_id: "wasm.remote".to_string(),
+            },
+            ContractError::InvalidIbcVersion {
+                expected: "reclaim-verify-1".to_string(),
+                version: "ics20-1".to_string(),
+            },
+            ContractError::InvalidIbcOrder {},
+            ContractError::ChannelNotOpen {
+                channel_id: "channel-0".to_string(),
+            },
+            ContractError::InvalidMinimumWitness {
+                minimum: 2,
+                count: 1,
+            },
+            ContractError::InvalidLimit {
+                field: "max_signatures".to_string(),
+                min: 1,
+                max: u32::MAX,
+            },
+            ContractError::PayoutAlreadyRegistered {},
+        ];
+        assert_eq!(errors.len(), crate::ERROR_CODES.len());
 
-        // Add three epochs
-        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-        execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
-        execute(deps.as_mut(), env, info, msg).unwrap();
+        for (error, (code, kind, _)) in errors.iter().zip(crate::ERROR_CODES) {
+            assert_eq!((error.code(), error.kind()), (*code, *kind));
+            let prefix = format!("[{}:{}] ", code, kind);
+            assert!(error.to_string().starts_with(&prefix), "{}", error);
+        }
 
-        let config = CONFIG.load(&deps.storage).unwrap();
-        assert_eq!(config.current_epoch, Uint128::from(3u128));
+        let published = include_str!("../schema/error_codes.json");
+        assert_eq!(published, crate::error_codes_json());
+    }
 
-        // Verify all epochs exist
-        assert!(EPOCHS.has(&deps.storage, 1));
-        assert!(EPOCHS.has(&deps.storage, 2));
-        assert!(EPOCHS.has(&deps.storage, 3));
+    #[test]
+    fn committed_schema_is_up_to_date() {
+        let api = cosmwasm_schema::generate_api! {
+            instantiate: InstantiateMsg,
+            execute: ExecuteMsg,
+            query: QueryMsg,
+            sudo: SudoMsg,
+        }
+        .render();
+        let committed = include_str!("../schema/reclaim-xion.json");
+        assert_eq!(committed, api.to_string().unwrap() + "\n");
     }
 
+    // Recorded output of the Reclaim JS SDK for the proof of create_proof_msg
+    const SDK_PROOF: &str = include_str!("../testdata/sdk_proof.json");
+
     #[test]
-    fn prevent_epoch_id_collision() {
+    fn verify_proof_accepts_sdk_proof() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
-
-        // Manually create conflicting epoch
         EPOCHS
-            .save(
-                &mut deps.storage,
-                1,
-                &Epoch {
-                    id: Uint128::one(),
-                    witness: vec![],
-                    timestamp_start: 0,
-                    timestamp_end: 0,
-                    minimum_witness_for_claim_creation: Uint128::zero(),
-                },
-            )
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
             .unwrap();
 
-        let info = mock_info(OWNER, &[]);
-        let msg = ExecuteMsg::AddEpoch {
-            witness: vec![],
-            minimum_witness: Uint128::one(),
+        let msg = format!(r#"{{"verify_proof":{{"proof":{}}}}}"#, SDK_PROOF);
+        let msg: ExecuteMsg = cosmwasm_std::from_json(msg.as_bytes()).unwrap();
+        let ExecuteMsg::VerifyProof(proof_msg) = msg.clone() else {
+            panic!("expected verify_proof");
         };
+        assert_eq!(proof_msg, create_proof_msg());
 
-        let res = execute(deps.as_mut(), mock_env(), info, msg);
-        assert_eq!(res, Err(ContractError::AlreadyExists {}));
+        let info = message_info(&addr(USER), &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let signer_event = res.events.iter().find(|e| e.ty == "signer").unwrap();
+        assert_eq!(signer_event.attributes[0].value, RECLAIM_ADDRESS);
     }
 
     #[test]
-    fn add_epoch_with_zero_witnesses() {
+    fn sdk_proof_round_trips() {
+        let sdk: SdkProof = cosmwasm_std::from_json(SDK_PROOF.as_bytes()).unwrap();
+        let json = cosmwasm_std::to_json_vec(&sdk).unwrap();
+        assert_eq!(cosmwasm_std::from_json::<SdkProof>(&json).unwrap(), sdk);
+
+        let proof = Proof::try_from(sdk.clone()).unwrap();
+        assert_eq!(proof, create_proof_msg().proof);
+
+        // the witnesses are not part of the contract proof
+        let back = SdkProof::from(proof.clone());
+        assert_eq!(back.witnesses, vec![]);
+        assert_eq!(
+            SdkProof {
+                witnesses: vec![],
+                ..sdk
+            },
+            back
+        );
+        assert_eq!(Proof::try_from(back).unwrap(), proof);
+
+        // the contract format still serialises as before
+        let msg = create_proof_msg();
+        let json = cosmwasm_std::to_json_vec(&msg).unwrap();
+        assert_eq!(cosmwasm_std::from_json::<ProofMsg>(&json).unwrap(), msg);
+    }
+
+    #[test]
+    fn malformed_sdk_proof_is_rejected() {
+        let sdk: SdkProof = cosmwasm_std::from_json(SDK_PROOF.as_bytes()).unwrap();
+
+        let mut other_identifier = sdk.clone();
+        other_identifier.identifier = ZERO_ADDRESS.to_string();
+        let err = Proof::try_from(other_identifier).unwrap_err();
+        assert!(matches!(err, ContractError::MalformedProof { .. }));
+
+        let mut tampered = sdk;
+        tampered
+            .extractedParameterValues
+            .as_mut()
+            .unwrap()
+            .insert("followers_count".to_string(), "100000".to_string());
+        let msg = cosmwasm_std::to_json_string(&tampered).unwrap();
+        let msg = format!(r#"{{"proof":{}}}"#, msg);
+        let err = cosmwasm_std::from_json::<ProofMsg>(msg.as_bytes()).unwrap_err();
+        assert!(err.to_string().contains("[28:malformed_proof]"), "{}", err);
+    }
+
+    // Reverses the key order of every object, which canonicalization has to undo
+    fn reversed(value: JsonValue) -> JsonValue {
+        match value {
+            JsonValue::Array(items) => JsonValue::Array(items.into_iter().map(reversed).collect()),
+            JsonValue::Object(entries) => JsonValue::Object(
+                entries
+                    .into_iter()
+                    .rev()
+                    .map(|(key, value)| (key, reversed(value)))
+                    .collect(),
+            ),
+            value => value,
+        }
+    }
+
+    #[test]
+    fn recorded_claim_info_is_canonical() {
+        let info = create_proof_msg().proof.claimInfo;
+        assert_eq!(
+            canonicalize_json(&info.parameters).unwrap(),
+            info.parameters
+        );
+        assert_eq!(canonicalize_json(&info.context).unwrap(), info.context);
+    }
+
+    #[test]
+    fn verify_proof_canonicalizes_structured_claim_info() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
+        EPOCHS
+            .save(deps.as_mut().storage, 1, &create_test_epoch())
+            .unwrap();
 
-        let info = mock_info(OWNER, &[]);
-        let msg = ExecuteMsg::AddEpoch {
-            witness: vec![],
-            minimum_witness: Uint128::zero(),
+        // The claim info as a client that re-serialised the JSON would send it
+        let expected = create_proof_msg();
+        let structured = |text: &str| {
+            let value: JsonValue = cosmwasm_std::from_json(text.as_bytes()).unwrap();
+            cosmwasm_std::to_json_string(&reversed(value)).unwrap()
         };
+        let parameters = structured(&expected.proof.claimInfo.parameters);
+        assert_ne!(parameters, expected.proof.claimInfo.parameters);
+        let msg = format!(
+            r#"{{"verify_proof":{{"proof":{{"claimInfo":{{"provider":"http","parameters":{},"context":{}}},"signedClaim":{}}}}}}}"#,
+            parameters,
+            structured(&expected.proof.claimInfo.context),
+            cosmwasm_std::to_json_string(&expected.proof.signedClaim).unwrap(),
+        );
+        let msg: ExecuteMsg = cosmwasm_std::from_json(msg.as_bytes()).unwrap();
+        assert_eq!(msg, ExecuteMsg::VerifyProof(expected));
 
+        let info = message_info(&addr(USER), &[]);
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
-        assert!(epoch.witness.is_empty());
-        assert_eq!(epoch.minimum_witness_for_claim_creation, Uint128::zero());
+        // floats have no canonical form
+        let msg = r#"{"proof":{"claimInfo":{"provider":"http","parameters":{"a":1.5},"context":""},"signedClaim":{"claim":{"identifier":"0x","owner":"0x","epoch":1,"timestampS":1},"signatures":[]}}}"#;
+        assert!(cosmwasm_std::from_json::<ProofMsg>(msg.as_bytes()).is_err());
     }
 
     #[test]
-    fn query_epoch_after_creation() {
+    fn multi_witness_epoch_from_test_support() {
         let mut deps = mock_dependencies();
         setup_contract(deps.as_mut());
+        let witnesses = testing::test_witnesses(7);
+        let epoch = testing::test_epoch(1, &witnesses, 4);
+        EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
 
-        let witness = vec![Witness {
-            address: ZERO_ADDRESS.to_string(),
-            host: "https://query.com".to_string(),
-        }];
+        let env = mock_env();
+        let proof = testing::test_proof(&epoch, &witnesses, env.block.time);
+        let signed = proof.signedClaim.signatures.len();
+        assert!((1..=4).contains(&signed));
+
+        let msg = ProofMsg {
+            proof,
+            domain: None,
+            owner_signature: None,
+        };
+        let info = message_info(&addr(USER), &[]);
+        let res = verify_proof(deps.as_mut(), msg, env, info).unwrap();
+        let signers = res.events.iter().filter(|e| e.ty == "signer").count();
+        assert_eq!(signers, signed);
+    }
 
-        let info = mock_info(OWNER, &[]);
+    #[test]
+    fn keyed_witnesses_from_test_support() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let env = mock_env();
+        let witnesses = vec![
+            testing::TestWitness::new(1),
+            testing::TestWitness::secp256k1(2),
+            testing::TestWitness::ed25519(3),
+        ];
         let msg = ExecuteMsg::AddEpoch {
-            witness: witness.clone(),
-            minimum_witness: Uint128::from(5u128),
+            witness: witnesses
+                .iter()
+                .map(testing::TestWitness::witness)
+                .collect(),
+            minimum_witness: Uint128::new(3),
+            policy: EpochPolicy::default(),
+            domain: None,
         };
-        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            message_info(&addr(OWNER), &[]),
+            msg,
+        )
+        .unwrap();
 
-        // Query the epoch
-        let res = query_epoch_id(deps.as_ref(), 1).unwrap();
-        assert_eq!(res.epoch.id, Uint128::one());
-        assert_eq!(res.epoch.witness, witness);
+        let epoch = EPOCHS.load(&deps.storage, 1).unwrap();
+        let proof = testing::test_proof(&epoch, &witnesses, env.block.time);
+        let signed = proof.signedClaim.signatures.len();
+        let msg = ProofMsg {
+            proof,
+            domain: None,
+            owner_signature: None,
+        };
+        let info = message_info(&addr(USER), &[]);
+        let res = verify_proof(deps.as_mut(), msg, env.clone(), info).unwrap();
+        let signers = res.events.iter().filter(|e| e.ty == "signer").count();
+        assert_eq!(signers, signed);
+
+        for (witness, payout) in witnesses.iter().zip(["payout1", "payout2", "payout3"]) {
+            let message = payout_registration_message(&env.contract.address, &addr(payout));
+            let register = ExecuteMsg::RegisterWitnessPayout {
+                witness: witness.address(),
+                signature: witness.sign_message(&message),
+                domain: None,
+            };
+            let info = message_info(&addr(payout), &[]);
+            execute(deps.as_mut(), env.clone(), info, register).unwrap();
+        }
     }
 
     #[test]
-    fn verify_proof_successfully() {
+    fn proof_of_ended_epoch_from_test_support() {
         let mut deps = mock_dependencies();
-        let env = mock_env();
+        setup_contract(deps.as_mut());
+        let witnesses = testing::test_witnesses(3);
+        let epoch = Epoch {
+            ended: true,
+            ..testing::test_epoch(1, &witnesses, 2)
+        };
+        EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
 
-        let owner = "owner0000";
-        let msg = InstantiateMsg {
-            owner: owner.to_string(),
+        let env = mock_env();
+        let msg = ProofMsg {
+            proof: testing::test_proof(&epoch, &witnesses, env.block.time),
+            domain: None,
+            owner_signature: None,
         };
-        let info = mock_info(owner, &[]);
-        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let info = message_info(&addr(USER), &[]);
+        let err = verify_proof(deps.as_mut(), msg, env, info).unwrap_err();
+        assert_eq!(err, ContractError::EpochEnded {});
+    }
 
-        EPOCHS
-            .save(deps.as_mut().storage, 1, &create_test_epoch())
-            .unwrap();
+    #[test]
+    fn malformed_signature_from_test_support() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let witnesses = testing::test_witnesses(1);
+        let epoch = testing::test_epoch(1, &witnesses, 1);
+        EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
 
-        let proof = create_proof_msg();
+        let claim_info = testing::test_claim_info();
+        let claim = testing::test_claim(&claim_info, 1, 1748539856);
+        let mut proof = testing::sign_proof(claim_info, claim, &[&witnesses[0]]);
+        proof
+            .signedClaim
+            .signatures
+            .push(testing::malformed_signature());
 
-        // Verify the proof
-        let res = verify_proof(deps.as_mut(), proof, env.clone());
+        let msg = ProofMsg {
+            proof,
+            domain: None,
+            owner_signature: None,
+        };
+        let info = message_info(&addr(USER), &[]);
+        let err = verify_proof(deps.as_mut(), msg, mock_env(), info).unwrap_err();
+        assert_eq!(err, ContractError::InvalidSignature { index: 1 });
+    }
 
-        // Should succeed
-        assert!(res.is_ok());
+    #[test]
+    fn test_support_is_deterministic() {
+        let witness = testing::TestWitness::new(1);
+        let claim = testing::test_claim(&testing::test_claim_info(), 1, 1);
+        assert_eq!(
+            witness.sign(&claim),
+            testing::TestWitness::new(1).sign(&claim)
+        );
+        assert_eq!(
+            recover_eth_address(&claim.serialise(), witness.sign(&claim)).unwrap(),
+            witness.address()
+        );
+        assert_ne!(witness.address(), testing::TestWitness::new(2).address());
+    }
 
-        // Check events contain the expected signer
-        let response = res.unwrap();
-        let signer_event = response.events.iter().find(|e| e.ty == "signer");
-        assert!(signer_event.is_some());
-        assert_eq!(signer_event.unwrap().attributes[0].value, RECLAIM_ADDRESS);
+    #[test]
+    fn epoch_without_witnesses_accepts_nothing() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let epoch = testing::test_epoch(1, &[], 2);
+        EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
+
+        let claim_info = testing::test_claim_info();
+        let claim = testing::test_claim(&claim_info, 1, 1748539856);
+        let msg = ProofMsg {
+            proof: testing::sign_proof(claim_info, claim, &[]),
+            domain: None,
+            owner_signature: None,
+        };
+        let info = message_info(&addr(USER), &[]);
+        let err = verify_proof(deps.as_mut(), msg, mock_env(), info).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::WitnessMismatchErr {
+                required: 1,
+                signed: 0
+            }
+        );
     }
 
     #[test]
-    fn verify_proof_with_hash_mismatch() {
+    fn oversized_proofs_are_rejected_before_hashing() {
         let mut deps = mock_dependencies();
-        let env = mock_env();
+        setup_contract(deps.as_mut());
+        let witnesses = testing::test_witnesses(2);
+        let epoch = testing::test_epoch(1, &witnesses, 1);
+        EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
+        let limits = SizeLimits {
+            max_parameters_bytes: 64,
+            max_context_bytes: 256,
+            max_signatures: 1,
+            max_witnesses: 2,
+        };
+        let info = message_info(&addr(OWNER), &[]);
+        let msg = ExecuteMsg::UpdateLimits { limits };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
-        let owner = "owner0000";
-        let msg = InstantiateMsg {
-            owner: owner.to_string(),
+        // the identifier no longer matches, but the size is checked first
+        let proof = testing::test_proof(&epoch, &witnesses, mock_env().block.time);
+        let mut oversized = proof.clone();
+        oversized.claimInfo.parameters = format!(r#"{{"body":"{}"}}"#, "a".repeat(64));
+        let mut long_context = proof.clone();
+        long_context.claimInfo.context = format!(r#"{{"padding":"{}"}}"#, "a".repeat(256));
+        let mut too_many = proof.clone();
+        too_many.signedClaim.signatures = vec![testing::malformed_signature(); 2];
+
+        let info = message_info(&addr(USER), &[]);
+        let verify = |deps: DepsMut, proof: Proof| {
+            let msg = ProofMsg {
+                proof,
+                domain: None,
+                owner_signature: None,
+            };
+            verify_proof(deps, msg, mock_env(), info.clone())
         };
-        let info = mock_info(owner, &[]);
-        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            verify(deps.as_mut(), oversized).unwrap_err(),
+            ContractError::InputTooLarge {
+                field: "parameters".to_string(),
+                size: 75,
+                max: 64,
+            }
+        );
+        let err = verify(deps.as_mut(), too_many).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "[30:too_many_items] signatures has 2 entries, the limit is 1"
+        );
+        let err = verify(deps.as_mut(), long_context).unwrap_err();
+        assert!(matches!(err, ContractError::InputTooLarge { field, .. } if field == "context"));
+        verify(deps.as_mut(), proof).unwrap();
+    }
 
-        EPOCHS
-            .save(deps.as_mut().storage, 1, &create_test_epoch())
-            .unwrap();
+    #[test]
+    fn owner_limits_witnesses_per_epoch() {
+        let mut deps = mock_dependencies();
+        setup_contract(deps.as_mut());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetLimits {}).unwrap();
+        let res: GetLimitsResponse = cosmwasm_std::from_json(res).unwrap();
+        assert_eq!(res.limits, SizeLimits::default());
 
-        // Create modified proof with invalid identifier
-        let mut proof = create_proof_msg();
-        proof.proof.signedClaim.claim.identifier = "invalid_hash".to_string();
+        let limits = SizeLimits {
+            max_witnesses: 2,
+            ..SizeLimits::default()
+        };
+        let msg = ExecuteMsg::UpdateLimits {
+            limits: limits.clone(),
+        };
+        let info = message_info(&addr(USER), &[]);
+        let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        let info = message_info(&addr(OWNER), &[]);
+        execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
-        // Verify should fail
-        let res = verify_proof(deps.as_mut(), proof, env);
-        assert_eq!(res, Err(ContractError::HashMismatchErr {}));
+        let add_epoch = |count| ExecuteMsg::AddEpoch {
+            witness: testing::test_witnesses(count)
+                .iter()
+                .map(testing::TestWitness::witness)
+                .collect(),
+            minimum_witness: Uint128::one(),
+            policy: EpochPolicy::default(),
+            domain: None,
+        };
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), add_epoch(3)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::TooManyItems {
+                field: "witness".to_string(),
+                count: 3,
+                max: 2,
+            }
+        );
+        execute(deps.as_mut(), mock_env(), info, add_epoch(2)).unwrap();
+
+        // governance can raise the limit without the owner
+        let msg = SudoMsg::UpdateConfig {
+            guardian: None,
+            epoch_delay: None,
+            fee: None,
+            treasury: None,
+            witness_share: None,
+            badge: None,
+            limits: Some(SizeLimits::default()),
+        };
+        sudo(deps.as_mut(), mock_env(), msg).unwrap();
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config.limits, SizeLimits::default());
     }
 
     #[test]
-    fn verify_proof_with_signature_error() {
+    fn limits_that_reject_every_input_are_refused() {
         let mut deps = mock_dependencies();
-        let env = mock_env();
+        setup_contract(deps.as_mut());
+        let info = message_info(&addr(OWNER), &[]);
+        let update = |limits| ExecuteMsg::UpdateLimits { limits };
 
-        let owner = "owner0000";
-        let msg = InstantiateMsg {
-            owner: owner.to_string(),
+        for (limits, field) in [
+            (
+                SizeLimits {
+                    max_parameters_bytes: 0,
+                    ..SizeLimits::default()
+                },
+                "max_parameters_bytes",
+            ),
+            (
+                SizeLimits {
+                    max_context_bytes: 0,
+                    ..SizeLimits::default()
+                },
+                "max_context_bytes",
+            ),
+            (
+                SizeLimits {
+                    max_signatures: 0,
+                    ..SizeLimits::default()
+                },
+                "max_signatures",
+            ),
+            (
+                SizeLimits {
+                    max_witnesses: 0,
+                    ..SizeLimits::default()
+                },
+                "max_witnesses",
+            ),
+        ] {
+            let res = execute(deps.as_mut(), mock_env(), info.clone(), update(limits));
+            assert!(
+                matches!(res, Err(ContractError::InvalidLimit { field: ref f, min: 1, .. }) if f == field),
+                "{:?}",
+                res
+            );
+        }
+
+        // Claim text over the parse time cap never reaches the configured limit
+        let limits = SizeLimits {
+            max_context_bytes: MAX_CLAIM_TEXT_BYTES as u32 + 1,
+            ..SizeLimits::default()
         };
-        let info = mock_info(owner, &[]);
-        instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), update(limits));
+        assert_eq!(
+            res,
+            Err(ContractError::InvalidLimit {
+                field: "max_context_bytes".to_string(),
+                min: 1,
+                max: MAX_CLAIM_TEXT_BYTES as u32,
+            })
+        );
 
-        EPOCHS
-            .save(deps.as_mut().storage, 1, &create_test_epoch())
-            .unwrap();
+        let msg = SudoMsg::UpdateConfig {
+            guardian: None,
+            epoch_delay: None,
+            fee: None,
+            treasury: None,
+            witness_share: None,
+            badge: None,
+            limits: Some(SizeLimits {
+                max_signatures: 0,
+                ..SizeLimits::default()
+            }),
+        };
+        let res = sudo(deps.as_mut(), mock_env(), msg);
+        assert!(matches!(res, Err(ContractError::InvalidLimit { .. })));
 
-        let mut proof = create_proof_msg();
+        let limits = SizeLimits {
+            max_parameters_bytes: MAX_CLAIM_TEXT_BYTES as u32,
+            max_context_bytes: 1,
+            max_signatures: 1,
+            max_witnesses: u32::MAX,
+        };
+        execute(deps.as_mut(), mock_env(), info, update(limits)).unwrap();
+    }
 
-        // Just change the recovery id from "1b" to "1c"
-        proof.proof.signedClaim.signatures[0] = "0x04fac06fb875a8a4896912461655f039b9b7726b1eacc1727f4b87c04b3971951387dc60b884e80e5c866722c1e34738a41c163f6c6bca2e33759a5ed34538201c".to_string();
+    // Signatures shaped like hex of any length, recoverable looking ones, and anything else
+    fn arbitrary_signature() -> impl Strategy<Value = String> {
+        prop_oneof![
+            "(0x)?[0-9a-f]{0,140}",
+            "0x[0-9a-f]{128}(1b|1c|00|ff)",
+            any::<String>(),
+        ]
+    }
 
-        // Verify should fail
-        let res = verify_proof(deps.as_mut(), proof, env);
-        assert_eq!(res, Err(ContractError::SignatureErr {}));
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        // Whatever a caller sends, verification returns an error instead of aborting,
+        // and the same proof always gets the same answer
+        #[test]
+        fn arbitrary_proofs_never_panic_and_verify_deterministically(
+            parameters in any::<String>(),
+            context in any::<String>(),
+            hashed in any::<bool>(),
+            epoch in 0u64..3,
+            timestamp in any::<u64>(),
+            signatures in prop::collection::vec(arbitrary_signature(), 0..4),
+        ) {
+            let claim_info = ClaimInfo {
+                provider: "http".to_string(),
+                parameters,
+                context,
+            };
+            let identifier = match hashed {
+                true => claim_info.hash(),
+                false => claim_info.parameters.clone(),
+            };
+            let msg = ProofMsg {
+                proof: Proof {
+                    claimInfo: claim_info,
+                    signedClaim: SignedClaim {
+                        claim: CompleteClaimData {
+                            identifier,
+                            owner: ZERO_ADDRESS.to_string(),
+                            epoch,
+                            timestampS: timestamp,
+                        },
+                        signatures,
+                    },
+                },
+                domain: None,
+                owner_signature: None,
+            };
+            let run = || {
+                let mut deps = mock_dependencies();
+                setup_contract(deps.as_mut());
+                let witnesses = testing::test_witnesses(3);
+                let epoch = testing::test_epoch(1, &witnesses, 2);
+                EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
+                verify_proof(deps.as_mut(), msg.clone(), mock_env(), message_info(&addr(USER), &[]))
+            };
+            prop_assert_eq!(run(), run());
+        }
+
+        #[test]
+        fn arbitrary_payout_signatures_never_panic(
+            witness in any::<String>(),
+            signature in arbitrary_signature(),
+        ) {
+            let mut deps = mock_dependencies();
+            setup_contract(deps.as_mut());
+            let res = register_witness_payout(deps.as_mut(), mock_env(), witness, signature, None, addr(USER));
+            prop_assert!(res.is_err());
+        }
     }
 }
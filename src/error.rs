@@ -1,24 +1,358 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{to_json_string, Coin, StdError};
+use reclaim_verifier_core::VerifierError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
-    #[error("{0}")]
+    #[error("[1:std] {0}")]
     Std(#[from] StdError),
 
-    #[error("Unauthorized")]
+    #[error("[2:unauthorized] Unauthorized")]
     Unauthorized {},
-    #[error("EPOCH id already exists")]
+    #[error("[3:already_exists] EPOCH id already exists")]
     AlreadyExists {},
 
-    #[error("Key recovery error")]
+    #[error("[4:pub_key] Key recovery error")]
     PubKeyErr {},
-    #[error("Signature not appropriate")]
+    #[error("[5:signature] Signature not appropriate")]
     SignatureErr {},
-    #[error("Hash mismatch")]
-    HashMismatchErr {},
-    #[error("Not enough witness")]
-    WitnessMismatchErr {},
-    #[error("Cannot find")]
-    NotFoundErr {},
+    #[error("[6:hash_mismatch] Hash mismatch: claim identifier {expected}, computed {computed}")]
+    HashMismatchErr { expected: String, computed: String },
+    #[error("[7:witness_mismatch] Not enough witness: required {required}, signed {signed}")]
+    WitnessMismatchErr { required: usize, signed: usize },
+    #[error("[8:invalid_signature] Signature {index} is malformed or does not verify")]
+    InvalidSignature { index: usize },
+    #[error("[9:unexpected_signer] Signature {index} is from {address}, which was not selected for the claim")]
+    UnexpectedSigner { address: String, index: usize },
+    #[error("[10:epoch_not_found] Epoch {id} not found")]
+    EpochNotFound { id: u128 },
+    #[error("[11:domain_not_found] Domain {name} not found")]
+    DomainNotFound { name: String },
+    #[error("[12:pending_change_not_found] Pending epoch change {id} not found")]
+    PendingChangeNotFound { id: u64 },
+    #[error("[13:insufficient_funds] Insufficient fee: required {required}, sent {sent}")]
+    InsufficientFunds { required: Coin, sent: Coin },
+    #[error("[14:wrong_denom] Wrong fee denomination: expected {expected}, got {received}")]
+    WrongDenom { expected: String, received: String },
+    #[error("[15:nothing_to_withdraw] No fees to withdraw")]
+    NothingToWithdraw {},
+    #[error("[16:invalid_fee_share] Witness fee share must be between 0 and 1")]
+    InvalidFeeShare {},
+    #[error("[17:payout_not_registered] Witness has no payout address registered")]
+    PayoutNotRegistered {},
+    #[error("[18:paused] Contract is paused")]
+    Paused {},
+    #[error("[19:cannot_revoke_owner] The owner role can only be transferred")]
+    CannotRevokeOwner {},
+    #[error("[20:timelock_not_expired] Epoch change is timelocked until {executable_after}")]
+    TimelockNotExpired { executable_after: u64 },
+    #[error("[21:epoch_ended] Epoch has ended")]
+    EpochEnded {},
+    #[error("[22:invalid_domain_name] Domain names must be 1 to 64 lowercase letters, digits, '-' or '_'")]
+    InvalidDomainName {},
+    #[error("[23:invalid_witness_key] Witness {address} has no valid public key for its signature scheme")]
+    InvalidWitnessKey { address: String },
+    #[error("[24:duplicate_signer] Witness {address} signed the claim more than once")]
+    DuplicateSigner { address: String },
+    #[error("[25:invalid_threshold] Threshold must be between 1 and the minimum witness count")]
+    InvalidThreshold {},
+    #[error("[26:domain_exists] Domain already exists")]
+    DomainExists {},
+    #[error("[27:provider_not_allowed] Provider {provider_hash} is not allowed in this domain")]
+    ProviderNotAllowed { provider_hash: String },
+    #[error("[28:malformed_proof] Malformed proof: {reason}")]
+    MalformedProof { reason: String },
+    #[error("[29:input_too_large] {field} is {size} bytes, the limit is {max}")]
+    InputTooLarge {
+        field: String,
+        size: usize,
+        max: u32,
+    },
+    #[error("[30:too_many_items] {field} has {count} entries, the limit is {max}")]
+    TooManyItems {
+        field: String,
+        count: usize,
+        max: u32,
+    },
+    #[error("[31:ibc_peer_not_allowed] Port {port_id} on {connection_id} is not allowed to open a channel")]
+    IbcPeerNotAllowed {
+        connection_id: String,
+        port_id: String,
+    },
+    #[error("[32:invalid_ibc_version] Channel version must be {expected}, got {version}")]
+    InvalidIbcVersion { expected: String, version: String },
+    #[error("[33:invalid_ibc_order] Verification channels must be unordered")]
+    InvalidIbcOrder {},
+    #[error("[34:channel_not_open] Channel {channel_id} is not an open verification channel")]
+    ChannelNotOpen { channel_id: String },
+    #[error("[35:invalid_minimum_witness] Minimum witness count {minimum} is more than the {count} witnesses of the epoch")]
+    InvalidMinimumWitness { minimum: u128, count: usize },
+    #[error("[36:invalid_limit] Limit {field} must be between {min} and {max}")]
+    InvalidLimit { field: String, min: u32, max: u32 },
+    #[error("[37:payout_already_registered] Witness already has a payout address")]
+    PayoutAlreadyRegistered {},
+}
+
+impl From<VerifierError> for ContractError {
+    fn from(err: VerifierError) -> Self {
+        match err {
+            VerifierError::SignatureErr {} => ContractError::SignatureErr {},
+            VerifierError::InvalidSignature { index } => ContractError::InvalidSignature { index },
+            VerifierError::InvalidContext(msg) => {
+                ContractError::Std(StdError::parse_err("ClaimContext", msg))
+            }
+            VerifierError::InvalidJson(reason) => ContractError::MalformedProof { reason },
+        }
+    }
+}
+
+impl ContractError {
+    /// Stable numeric code, never reused or renumbered across contract versions. It is
+    /// also the `[code:kind]` prefix of the error message.
+    pub fn code(&self) -> u32 {
+        match self {
+            ContractError::Std(_) => 1,
+            ContractError::Unauthorized { .. } => 2,
+            ContractError::AlreadyExists { .. } => 3,
+            ContractError::PubKeyErr { .. } => 4,
+            ContractError::SignatureErr { .. } => 5,
+            ContractError::HashMismatchErr { .. } => 6,
+            ContractError::WitnessMismatchErr { .. } => 7,
+            ContractError::InvalidSignature { .. } => 8,
+            ContractError::UnexpectedSigner { .. } => 9,
+            ContractError::EpochNotFound { .. } => 10,
+            ContractError::DomainNotFound { .. } => 11,
+            ContractError::PendingChangeNotFound { .. } => 12,
+            ContractError::InsufficientFunds { .. } => 13,
+            ContractError::WrongDenom { .. } => 14,
+            ContractError::NothingToWithdraw { .. } => 15,
+            ContractError::InvalidFeeShare { .. } => 16,
+            ContractError::PayoutNotRegistered { .. } => 17,
+            ContractError::Paused { .. } => 18,
+            ContractError::CannotRevokeOwner { .. } => 19,
+            ContractError::TimelockNotExpired { .. } => 20,
+            ContractError::EpochEnded { .. } => 21,
+            ContractError::InvalidDomainName { .. } => 22,
+            ContractError::InvalidWitnessKey { .. } => 23,
+            ContractError::DuplicateSigner { .. } => 24,
+            ContractError::InvalidThreshold { .. } => 25,
+            ContractError::DomainExists { .. } => 26,
+            ContractError::ProviderNotAllowed { .. } => 27,
+            ContractError::MalformedProof { .. } => 28,
+            ContractError::InputTooLarge { .. } => 29,
+            ContractError::TooManyItems { .. } => 30,
+            ContractError::IbcPeerNotAllowed { .. } => 31,
+            ContractError::InvalidIbcVersion { .. } => 32,
+            ContractError::InvalidIbcOrder { .. } => 33,
+            ContractError::ChannelNotOpen { .. } => 34,
+            ContractError::InvalidMinimumWitness { .. } => 35,
+            ContractError::InvalidLimit { .. } => 36,
+            ContractError::PayoutAlreadyRegistered { .. } => 37,
+        }
+    }
+
+    /// Machine-readable name of the error, stable like `code`
+    pub fn kind(&self) -> &'static str {
+        match self {
+            ContractError::Std(_) => "std",
+            ContractError::Unauthorized { .. } => "unauthorized",
+            ContractError::AlreadyExists { .. } => "already_exists",
+            ContractError::PubKeyErr { .. } => "pub_key",
+            ContractError::SignatureErr { .. } => "signature",
+            ContractError::HashMismatchErr { .. } => "hash_mismatch",
+            ContractError::WitnessMismatchErr { .. } => "witness_mismatch",
+            ContractError::InvalidSignature { .. } => "invalid_signature",
+            ContractError::UnexpectedSigner { .. } => "unexpected_signer",
+            ContractError::EpochNotFound { .. } => "epoch_not_found",
+            ContractError::DomainNotFound { .. } => "domain_not_found",
+            ContractError::PendingChangeNotFound { .. } => "pending_change_not_found",
+            ContractError::InsufficientFunds { .. } => "insufficient_funds",
+            ContractError::WrongDenom { .. } => "wrong_denom",
+            ContractError::NothingToWithdraw { .. } => "nothing_to_withdraw",
+            ContractError::InvalidFeeShare { .. } => "invalid_fee_share",
+            ContractError::PayoutNotRegistered { .. } => "payout_not_registered",
+            ContractError::Paused { .. } => "paused",
+            ContractError::CannotRevokeOwner { .. } => "cannot_revoke_owner",
+            ContractError::TimelockNotExpired { .. } => "timelock_not_expired",
+            ContractError::EpochEnded { .. } => "epoch_ended",
+            ContractError::InvalidDomainName { .. } => "invalid_domain_name",
+            ContractError::InvalidWitnessKey { .. } => "invalid_witness_key",
+            ContractError::DuplicateSigner { .. } => "duplicate_signer",
+            ContractError::InvalidThreshold { .. } => "invalid_threshold",
+            ContractError::DomainExists { .. } => "domain_exists",
+            ContractError::ProviderNotAllowed { .. } => "provider_not_allowed",
+            ContractError::MalformedProof { .. } => "malformed_proof",
+            ContractError::InputTooLarge { .. } => "input_too_large",
+            ContractError::TooManyItems { .. } => "too_many_items",
+            ContractError::IbcPeerNotAllowed { .. } => "ibc_peer_not_allowed",
+            ContractError::InvalidIbcVersion { .. } => "invalid_ibc_version",
+            ContractError::InvalidIbcOrder { .. } => "invalid_ibc_order",
+            ContractError::ChannelNotOpen { .. } => "channel_not_open",
+            ContractError::InvalidMinimumWitness { .. } => "invalid_minimum_witness",
+            ContractError::InvalidLimit { .. } => "invalid_limit",
+            ContractError::PayoutAlreadyRegistered { .. } => "payout_already_registered",
+        }
+    }
+}
+
+/// `(code, kind, message)` of every error, published as `schema/error_codes.json`
+pub const ERROR_CODES: &[(u32, &str, &str)] = &[
+    (
+        1,
+        "std",
+        "CosmWasm standard error, its message follows the prefix",
+    ),
+    (2, "unauthorized", "Unauthorized"),
+    (3, "already_exists", "EPOCH id already exists"),
+    (4, "pub_key", "Key recovery error"),
+    (5, "signature", "Signature not appropriate"),
+    (
+        6,
+        "hash_mismatch",
+        "Hash mismatch: claim identifier {expected}, computed {computed}",
+    ),
+    (
+        7,
+        "witness_mismatch",
+        "Not enough witness: required {required}, signed {signed}",
+    ),
+    (
+        8,
+        "invalid_signature",
+        "Signature {index} is malformed or does not verify",
+    ),
+    (
+        9,
+        "unexpected_signer",
+        "Signature {index} is from {address}, which was not selected for the claim",
+    ),
+    (10, "epoch_not_found", "Epoch {id} not found"),
+    (11, "domain_not_found", "Domain {name} not found"),
+    (
+        12,
+        "pending_change_not_found",
+        "Pending epoch change {id} not found",
+    ),
+    (
+        13,
+        "insufficient_funds",
+        "Insufficient fee: required {required}, sent {sent}",
+    ),
+    (
+        14,
+        "wrong_denom",
+        "Wrong fee denomination: expected {expected}, got {received}",
+    ),
+    (15, "nothing_to_withdraw", "No fees to withdraw"),
+    (
+        16,
+        "invalid_fee_share",
+        "Witness fee share must be between 0 and 1",
+    ),
+    (
+        17,
+        "payout_not_registered",
+        "Witness has no payout address registered",
+    ),
+    (18, "paused", "Contract is paused"),
+    (
+        19,
+        "cannot_revoke_owner",
+        "The owner role can only be transferred",
+    ),
+    (
+        20,
+        "timelock_not_expired",
+        "Epoch change is timelocked until {executable_after}",
+    ),
+    (21, "epoch_ended", "Epoch has ended"),
+    (
+        22,
+        "invalid_domain_name",
+        "Domain names must be 1 to 64 lowercase letters, digits, '-' or '_'",
+    ),
+    (
+        23,
+        "invalid_witness_key",
+        "Witness {address} has no valid public key for its signature scheme",
+    ),
+    (
+        24,
+        "duplicate_signer",
+        "Witness {address} signed the claim more than once",
+    ),
+    (
+        25,
+        "invalid_threshold",
+        "Threshold must be between 1 and the minimum witness count",
+    ),
+    (26, "domain_exists", "Domain already exists"),
+    (
+        27,
+        "provider_not_allowed",
+        "Provider {provider_hash} is not allowed in this domain",
+    ),
+    (28, "malformed_proof", "Malformed proof: {reason}"),
+    (
+        29,
+        "input_too_large",
+        "{field} is {size} bytes, the limit is {max}",
+    ),
+    (
+        30,
+        "too_many_items",
+        "{field} has {count} entries, the limit is {max}",
+    ),
+    (
+        31,
+        "ibc_peer_not_allowed",
+        "Port {port_id} on {connection_id} is not allowed to open a channel",
+    ),
+    (
+        32,
+        "invalid_ibc_version",
+        "Channel version must be {expected}, got {version}",
+    ),
+    (
+        33,
+        "invalid_ibc_order",
+        "Verification channels must be unordered",
+    ),
+    (
+        34,
+        "channel_not_open",
+        "Channel {channel_id} is not an open verification channel",
+    ),
+    (
+        35,
+        "invalid_minimum_witness",
+        "Minimum witness count {minimum} is more than the {count} witnesses of the epoch",
+    ),
+    (
+        36,
+        "invalid_limit",
+        "Limit {field} must be between {min} and {max}",
+    ),
+    (
+        37,
+        "payout_already_registered",
+        "Witness already has a payout address",
+    ),
+];
+
+/// `ERROR_CODES` as the JSON table committed in `schema/error_codes.json`, written by the
+/// schema binary
+pub fn error_codes_json() -> String {
+    let rows: Vec<String> = ERROR_CODES
+        .iter()
+        .map(|(code, kind, message)| {
+            format!(
+                "  {{\"code\": {}, \"kind\": \"{}\", \"message\": {}}}",
+                code,
+                kind,
+                to_json_string(message).unwrap_or_default()
+            )
+        })
+        .collect();
+    format!("[\n{}\n]\n", rows.join(",\n"))
 }
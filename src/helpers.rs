@@ -1,27 +1,629 @@
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
-use cosmwasm_std::{to_json_binary, Addr, CosmosMsg, StdResult, WasmMsg};
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Coin, CosmosMsg, Decimal, IbcAcknowledgement, IbcMsg,
+    IbcTimeout, QuerierWrapper, StdResult, Uint128, WasmMsg,
+};
 
-use crate::msg::ExecuteMsg;
+use crate::claims::Proof;
+use crate::msg::{
+    AllNftInfoResponse, ContractInfoResponse, ExecuteMsg, GetAllDomainsResponse,
+    GetAllEpochResponse, GetBadgeResponse, GetDomainResponse, GetEpochResponse,
+    GetFeeConfigResponse, GetIbcChannelsResponse, GetIbcPeersResponse, GetLimitsResponse,
+    GetPauseStateResponse, GetPendingEpochChangesResponse, GetProviderFeeResponse,
+    GetRoleMembersResponse, GetWitnessRewardsResponse, IbcAck, IbcPacketMsg, NftInfoResponse,
+    NumTokensResponse, OwnerOfResponse, ProofMsg, QueryMsg, TokensResponse,
+};
+use crate::state::{
+    BadgeConfig, DomainPolicy, EpochPolicy, IbcPeer, PauseScope, Role, SizeLimits, Witness,
+};
 
-/// CwTemplateContract is a wrapper around Addr that provides a lot of helpers
-/// for working with this.
+/// ReclaimVerifierContract is a wrapper around the verifier address, building its execute
+/// messages and running its queries for contracts that integrate with it.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
-pub struct CwTemplateContract(pub Addr);
+pub struct ReclaimVerifierContract(pub Addr);
 
-impl CwTemplateContract {
+impl ReclaimVerifierContract {
     pub fn addr(&self) -> Addr {
         self.0.clone()
     }
 
     pub fn call<T: Into<ExecuteMsg>>(&self, msg: T) -> StdResult<CosmosMsg> {
+        self.call_with_funds(msg, vec![])
+    }
+
+    pub fn call_with_funds<T: Into<ExecuteMsg>>(
+        &self,
+        msg: T,
+        funds: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
         let msg = to_json_binary(&msg.into())?;
         Ok(WasmMsg::Execute {
             contract_addr: self.addr().into(),
             msg,
-            funds: vec![],
+            funds,
         }
         .into())
     }
+
+    pub fn query<T: DeserializeOwned>(
+        &self,
+        querier: &QuerierWrapper,
+        msg: &QueryMsg,
+    ) -> StdResult<T> {
+        querier.query_wasm_smart(self.addr(), msg)
+    }
+
+    /// `funds` pays the verification fee, anything above it is refunded to the caller.
+    /// `owner_signature` names the badge holder when the claim owner is not an address on
+    /// this chain, see `badge_owner_message`.
+    pub fn verify_proof(
+        &self,
+        proof: Proof,
+        domain: Option<String>,
+        owner_signature: Option<String>,
+        funds: Vec<Coin>,
+    ) -> StdResult<CosmosMsg> {
+        self.call_with_funds(
+            ExecuteMsg::VerifyProof(ProofMsg {
+                proof,
+                domain,
+                owner_signature,
+            }),
+            funds,
+        )
+    }
+
+    pub fn add_epoch(
+        &self,
+        witness: Vec<Witness>,
+        minimum_witness: Uint128,
+        policy: EpochPolicy,
+        domain: Option<String>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::AddEpoch {
+            witness,
+            minimum_witness,
+            policy,
+            domain,
+        })
+    }
+
+    pub fn update_witnesses(
+        &self,
+        witness: Vec<Witness>,
+        minimum_witness: Uint128,
+        policy: EpochPolicy,
+        domain: Option<String>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateWitnesses {
+            witness,
+            minimum_witness,
+            policy,
+            domain,
+        })
+    }
+
+    pub fn create_domain(
+        &self,
+        name: impl Into<String>,
+        admin: impl Into<String>,
+        policy: DomainPolicy,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::CreateDomain {
+            name: name.into(),
+            admin: admin.into(),
+            policy,
+        })
+    }
+
+    pub fn update_domain(
+        &self,
+        name: impl Into<String>,
+        admin: Option<String>,
+        policy: Option<DomainPolicy>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateDomain {
+            name: name.into(),
+            admin,
+            policy,
+        })
+    }
+
+    pub fn set_epoch_delay(&self, seconds: u64) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::SetEpochDelay { seconds })
+    }
+
+    pub fn execute_epoch_change(&self, id: u64) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::ExecuteEpochChange { id })
+    }
+
+    pub fn cancel_epoch_change(&self, id: u64) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::CancelEpochChange { id })
+    }
+
+    pub fn update_badge_config(&self, badge: Option<BadgeConfig>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateBadgeConfig { badge })
+    }
+
+    /// `None` keeps a value, `Some(None)` clears it
+    pub fn update_fee_config(
+        &self,
+        fee: Option<Option<Coin>>,
+        treasury: Option<Option<String>>,
+        witness_share: Option<Option<Decimal>>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateFeeConfig {
+            fee,
+            treasury,
+            witness_share,
+        })
+    }
+
+    pub fn set_provider_fee(
+        &self,
+        provider_hash: impl Into<String>,
+        fee: Option<Coin>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::SetProviderFee {
+            provider_hash: provider_hash.into(),
+            fee,
+        })
+    }
+
+    pub fn withdraw_fees(&self) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::WithdrawFees {})
+    }
+
+    pub fn register_witness_payout(
+        &self,
+        witness: impl Into<String>,
+        signature: impl Into<String>,
+        domain: Option<String>,
+    ) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::RegisterWitnessPayout {
+            witness: witness.into(),
+            signature: signature.into(),
+            domain,
+        })
+    }
+
+    pub fn claim_witness_rewards(&self, witness: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::ClaimWitnessRewards {
+            witness: witness.into(),
+        })
+    }
+
+    pub fn pause(&self, scope: PauseScope) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Pause { scope })
+    }
+
+    pub fn unpause(&self, scope: PauseScope) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::Unpause { scope })
+    }
+
+    pub fn update_guardian(&self, guardian: Option<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateGuardian { guardian })
+    }
+
+    pub fn grant_role(&self, role: Role, address: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::GrantRole {
+            role,
+            address: address.into(),
+        })
+    }
+
+    pub fn revoke_role(&self, role: Role, address: impl Into<String>) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::RevokeRole {
+            role,
+            address: address.into(),
+        })
+    }
+
+    pub fn update_limits(&self, limits: SizeLimits) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::UpdateLimits { limits })
+    }
+
+    pub fn allow_ibc_peer(&self, peer: IbcPeer) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::AllowIbcPeer { peer })
+    }
+
+    pub fn disallow_ibc_peer(&self, peer: IbcPeer) -> StdResult<CosmosMsg> {
+        self.call(ExecuteMsg::DisallowIbcPeer { peer })
+    }
+
+    pub fn epoch(
+        &self,
+        querier: &QuerierWrapper,
+        id: u128,
+        domain: Option<String>,
+    ) -> StdResult<GetEpochResponse> {
+        self.query(querier, &QueryMsg::GetEpoch { id, domain })
+    }
+
+    pub fn all_epochs(
+        &self,
+        querier: &QuerierWrapper,
+        domain: Option<String>,
+    ) -> StdResult<GetAllEpochResponse> {
+        self.query(querier, &QueryMsg::GetAllEpoch { domain })
+    }
+
+    pub fn domain(
+        &self,
+        querier: &QuerierWrapper,
+        name: impl Into<String>,
+    ) -> StdResult<GetDomainResponse> {
+        self.query(querier, &QueryMsg::GetDomain { name: name.into() })
+    }
+
+    pub fn all_domains(&self, querier: &QuerierWrapper) -> StdResult<GetAllDomainsResponse> {
+        self.query(querier, &QueryMsg::GetAllDomains {})
+    }
+
+    pub fn badge(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: impl Into<String>,
+    ) -> StdResult<GetBadgeResponse> {
+        let token_id = token_id.into();
+        self.query(querier, &QueryMsg::GetBadge { token_id })
+    }
+
+    /// cw721 `owner_of`, the holder of a badge
+    pub fn owner_of(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: impl Into<String>,
+    ) -> StdResult<OwnerOfResponse> {
+        let token_id = token_id.into();
+        let msg = QueryMsg::OwnerOf {
+            token_id,
+            include_expired: None,
+        };
+        self.query(querier, &msg)
+    }
+
+    /// cw721 `nft_info`, the metadata of a badge
+    pub fn nft_info(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: impl Into<String>,
+    ) -> StdResult<NftInfoResponse> {
+        let token_id = token_id.into();
+        self.query(querier, &QueryMsg::NftInfo { token_id })
+    }
+
+    /// cw721 `all_nft_info` of a badge, its holder and metadata
+    pub fn all_nft_info(
+        &self,
+        querier: &QuerierWrapper,
+        token_id: impl Into<String>,
+    ) -> StdResult<AllNftInfoResponse> {
+        let token_id = token_id.into();
+        let msg = QueryMsg::AllNftInfo {
+            token_id,
+            include_expired: None,
+        };
+        self.query(querier, &msg)
+    }
+
+    /// cw721 `tokens`, a page of the badges held by `owner`
+    pub fn tokens(
+        &self,
+        querier: &QuerierWrapper,
+        owner: impl Into<String>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        let msg = QueryMsg::Tokens {
+            owner: owner.into(),
+            start_after,
+            limit,
+        };
+        self.query(querier, &msg)
+    }
+
+    /// cw721 `all_tokens`, a page of every badge
+    pub fn all_tokens(
+        &self,
+        querier: &QuerierWrapper,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    ) -> StdResult<TokensResponse> {
+        self.query(querier, &QueryMsg::AllTokens { start_after, limit })
+    }
+
+    pub fn num_tokens(&self, querier: &QuerierWrapper) -> StdResult<NumTokensResponse> {
+        self.query(querier, &QueryMsg::NumTokens {})
+    }
+
+    /// cw721 `contract_info`, the name and symbol of the badge collection
+    pub fn contract_info(&self, querier: &QuerierWrapper) -> StdResult<ContractInfoResponse> {
+        self.query(querier, &QueryMsg::ContractInfo {})
+    }
+
+    pub fn fee_config(&self, querier: &QuerierWrapper) -> StdResult<GetFeeConfigResponse> {
+        self.query(querier, &QueryMsg::GetFeeConfig {})
+    }
+
+    pub fn provider_fee(
+        &self,
+        querier: &QuerierWrapper,
+        provider_hash: impl Into<String>,
+    ) -> StdResult<GetProviderFeeResponse> {
+        let provider_hash = provider_hash.into();
+        self.query(querier, &QueryMsg::GetProviderFee { provider_hash })
+    }
+
+    pub fn witness_rewards(
+        &self,
+        querier: &QuerierWrapper,
+        witness: impl Into<String>,
+    ) -> StdResult<GetWitnessRewardsResponse> {
+        let witness = witness.into();
+        self.query(querier, &QueryMsg::GetWitnessRewards { witness })
+    }
+
+    pub fn pause_state(&self, querier: &QuerierWrapper) -> StdResult<GetPauseStateResponse> {
+        self.query(querier, &QueryMsg::GetPauseState {})
+    }
+
+    pub fn role_members(
+        &self,
+        querier: &QuerierWrapper,
+        role: Role,
+    ) -> StdResult<GetRoleMembersResponse> {
+        self.query(querier, &QueryMsg::GetRoleMembers { role })
+    }
+
+    pub fn pending_epoch_changes(
+        &self,
+        querier: &QuerierWrapper,
+    ) -> StdResult<GetPendingEpochChangesResponse> {
+        self.query(querier, &QueryMsg::GetPendingEpochChanges {})
+    }
+
+    pub fn limits(&self, querier: &QuerierWrapper) -> StdResult<GetLimitsResponse> {
+        self.query(querier, &QueryMsg::GetLimits {})
+    }
+
+    pub fn ibc_peers(&self, querier: &QuerierWrapper) -> StdResult<GetIbcPeersResponse> {
+        self.query(querier, &QueryMsg::GetIbcPeers {})
+    }
+
+    pub fn ibc_channels(&self, querier: &QuerierWrapper) -> StdResult<GetIbcChannelsResponse> {
+        self.query(querier, &QueryMsg::GetIbcChannels {})
+    }
+}
+
+/// The packet a contract on another chain sends over its verification channel to have
+/// `proof` verified by the contract. A packet that times out was never verified, and can
+/// be sent again.
+pub fn ibc_verify_proof(
+    channel_id: impl Into<String>,
+    proof: Proof,
+    domain: Option<String>,
+    owner_signature: Option<String>,
+    timeout: IbcTimeout,
+) -> StdResult<IbcMsg> {
+    Ok(IbcMsg::SendPacket {
+        channel_id: channel_id.into(),
+        data: to_json_binary(&IbcPacketMsg::VerifyProof(ProofMsg {
+            proof,
+            domain,
+            owner_signature,
+        }))?,
+        timeout,
+    })
+}
+
+/// Reads the acknowledgement of a packet built by `ibc_verify_proof`
+pub fn parse_ibc_ack(ack: &IbcAcknowledgement) -> StdResult<IbcAck> {
+    from_json(&ack.data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{
+        badge_owner_message, execute, instantiate, query, sudo, BADGE_NAME, BADGE_SYMBOL,
+    };
+    use crate::msg::InstantiateMsg;
+    use crate::state::BadgeAttribute;
+    use crate::testing::{owned_test_proof, test_epoch, test_proof, test_witnesses, TestWitness};
+    use cosmwasm_std::Timestamp;
+    use cw_multi_test::{App, ContractWrapper, Executor};
+
+    // Instantiates the contract with `badge`, returning its client
+    fn setup(app: &mut App, badge: Option<BadgeConfig>) -> ReclaimVerifierContract {
+        let owner = app.api().addr_make("owner");
+        let code = app.store_code(Box::new(
+            ContractWrapper::new(execute, instantiate, query).with_sudo(sudo),
+        ));
+        let msg = InstantiateMsg {
+            owner: owner.to_string(),
+            badge,
+            guardian: None,
+        };
+        let contract = app
+            .instantiate_contract(code, owner, &msg, &[], "reclaim", None)
+            .unwrap();
+        ReclaimVerifierContract(contract)
+    }
+
+    // Adds an epoch of `witnesses` and has `user` verify a claim by `claim_owner`, which is
+    // a key that is not an address on the chain and names `user` as the badge holder.
+    // Returns the badge token id.
+    fn mint_badge(
+        app: &mut App,
+        verifier: &ReclaimVerifierContract,
+        witnesses: &[TestWitness],
+        claim_owner: &TestWitness,
+    ) -> String {
+        let owner = app.api().addr_make("owner");
+        let user = app.api().addr_make("user");
+        let witness = witnesses.iter().map(TestWitness::witness).collect();
+        let add_epoch = verifier
+            .add_epoch(witness, Uint128::one(), EpochPolicy::default(), None)
+            .unwrap();
+        app.execute(owner, add_epoch).unwrap();
+        let epoch = verifier.epoch(&app.wrap(), 1, None).unwrap().epoch;
+
+        let time = app.block_info().time;
+        let proof = owned_test_proof(&epoch, witnesses, time, &claim_owner.address());
+        let message = badge_owner_message(&verifier.addr(), &user);
+        let signature = claim_owner.sign_message(&message);
+        let verify = verifier
+            .verify_proof(proof.clone(), None, Some(signature), vec![])
+            .unwrap();
+        app.execute(user, verify).unwrap();
+        proof.signedClaim.claim.identifier
+    }
+
+    fn badge_config() -> Option<BadgeConfig> {
+        Some(BadgeConfig {
+            extracted_parameters: vec!["username".to_string()],
+        })
+    }
+
+    #[test]
+    fn typed_client_drives_the_contract() {
+        let mut app = App::default();
+        let owner = app.api().addr_make("owner");
+        let user = app.api().addr_make("user");
+        let verifier = setup(&mut app, None);
+
+        let witnesses = test_witnesses(2);
+        let witness: Vec<Witness> = witnesses.iter().map(TestWitness::witness).collect();
+        let add_epoch = verifier
+            .add_epoch(
+                witness.clone(),
+                Uint128::one(),
+                EpochPolicy::default(),
+                None,
+            )
+            .unwrap();
+        app.execute(owner.clone(), add_epoch).unwrap();
+        assert_eq!(verifier.all_epochs(&app.wrap(), None).unwrap().ids, vec![1]);
+        let epoch = verifier.epoch(&app.wrap(), 1, None).unwrap().epoch;
+        assert_eq!(epoch.witness, witness);
+
+        let proof = test_proof(&epoch, &witnesses, app.block_info().time);
+        let verify = verifier.verify_proof(proof, None, None, vec![]).unwrap();
+        let res = app.execute(user, verify).unwrap();
+        assert!(res.events.iter().any(|e| e.ty == "wasm-signer"));
+
+        let pause = verifier.pause(PauseScope::Verification).unwrap();
+        app.execute(owner, pause).unwrap();
+        assert!(verifier.pause_state(&app.wrap()).unwrap().verification);
+    }
+
+    #[test]
+    fn verify_proof_sends_the_owner_signature() {
+        let mut app = App::default();
+        let user = app.api().addr_make("user");
+        let verifier = setup(&mut app, badge_config());
+        let token_id = mint_badge(
+            &mut app,
+            &verifier,
+            &test_witnesses(2),
+            &TestWitness::new(9),
+        );
+
+        // Without the signature the badge would not have been minted
+        let badge = verifier.badge(&app.wrap(), token_id).unwrap().badge;
+        assert_eq!(badge.owner, user);
+    }
+
+    #[test]
+    fn ibc_verify_proof_sends_the_owner_signature() {
+        let witnesses = test_witnesses(1);
+        let epoch = test_epoch(1, &witnesses, 1);
+        let proof = test_proof(&epoch, &witnesses, Timestamp::from_seconds(1));
+        let timeout = IbcTimeout::with_timestamp(Timestamp::from_seconds(2));
+        let signature = Some("0x01".to_string());
+        let packet = ibc_verify_proof("channel-0", proof.clone(), None, signature.clone(), timeout);
+        let IbcMsg::SendPacket { data, .. } = packet.unwrap() else {
+            unreachable!()
+        };
+        let IbcPacketMsg::VerifyProof(msg) = from_json(data).unwrap();
+        assert_eq!(
+            msg,
+            ProofMsg {
+                proof,
+                domain: None,
+                owner_signature: signature,
+            }
+        );
+    }
+
+    #[test]
+    fn owner_of_names_the_badge_holder() {
+        let mut app = App::default();
+        let user = app.api().addr_make("user");
+        let verifier = setup(&mut app, badge_config());
+        let token_id = mint_badge(
+            &mut app,
+            &verifier,
+            &test_witnesses(2),
+            &TestWitness::new(9),
+        );
+
+        let res = verifier.owner_of(&app.wrap(), token_id).unwrap();
+        assert_eq!(res.owner, user.to_string());
+        assert!(res.approvals.is_empty());
+    }
+
+    #[test]
+    fn nft_info_returns_the_badge_metadata() {
+        let mut app = App::default();
+        let verifier = setup(&mut app, badge_config());
+        let token_id = mint_badge(
+            &mut app,
+            &verifier,
+            &test_witnesses(2),
+            &TestWitness::new(9),
+        );
+
+        let res = verifier.nft_info(&app.wrap(), token_id).unwrap();
+        assert_eq!(res.token_uri, None);
+        assert_eq!(
+            res.extension.extracted_parameters,
+            vec![BadgeAttribute {
+                key: "username".to_string(),
+                value: "reclaim".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn all_tokens_lists_every_badge() {
+        let mut app = App::default();
+        let verifier = setup(&mut app, badge_config());
+        let token_id = mint_badge(
+            &mut app,
+            &verifier,
+            &test_witnesses(2),
+            &TestWitness::new(9),
+        );
+
+        let res = verifier.all_tokens(&app.wrap(), None, None).unwrap();
+        assert_eq!(res.tokens, vec![token_id.clone()]);
+        let res = verifier
+            .all_tokens(&app.wrap(), Some(token_id), None)
+            .unwrap();
+        assert!(res.tokens.is_empty());
+    }
+
+    #[test]
+    fn contract_info_names_the_collection() {
+        let mut app = App::default();
+        let verifier = setup(&mut app, None);
+
+        let res = verifier.contract_info(&app.wrap()).unwrap();
+        assert_eq!(res.name, BADGE_NAME);
+        assert_eq!(res.symbol, BADGE_SYMBOL);
+    }
 }
@@ -0,0 +1,451 @@
+//! Verification over IBC. A contract on a remote chain opens a channel to this contract from
+//! a whitelisted port, sends `IbcPacketMsg::VerifyProof` packets over it and receives an
+//! `IbcAck` with the verified claim or the error.
+//!
+//! Packets are verified against the epochs of this chain, the way `verify_proof` checks a
+//! proof, except that no fee is charged and no badge is minted: there is no sender on this
+//! chain to pay for them.
+
+use cosmwasm_std::{
+    from_json, to_json_binary, Addr, Binary, Deps, DepsMut, Env, Event, IbcBasicResponse,
+    IbcChannel, IbcChannelCloseMsg, IbcChannelConnectMsg, IbcChannelOpenMsg,
+    IbcChannelOpenResponse, IbcMsg, IbcOrder, IbcPacketAckMsg, IbcPacketReceiveMsg,
+    IbcPacketTimeoutMsg, IbcReceiveResponse, Never, Response, StdResult,
+};
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use crate::contract::{check_claim, check_witnesses};
+use crate::error::ContractError;
+use crate::msg::{IbcAck, IbcPacketMsg, ProofMsg, VerificationResult};
+use crate::state::{get_ibc_channels, IbcChannelInfo, IbcPeer, CONFIG, IBC_CHANNELS, IBC_PEERS};
+
+pub const IBC_VERSION: &str = "reclaim-verify-1";
+pub const IBC_ORDER: IbcOrder = IbcOrder::Unordered;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_open(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelOpenMsg,
+) -> Result<IbcChannelOpenResponse, ContractError> {
+    if let IbcChannelOpenMsg::OpenTry {
+        counterparty_version,
+        ..
+    } = &msg
+    {
+        check_version(counterparty_version)?;
+    }
+    check_channel(deps.as_ref(), msg.channel())?;
+    Ok(None)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_connect(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelConnectMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    if let Some(counterparty_version) = msg.counterparty_version() {
+        check_version(counterparty_version)?;
+    }
+    let channel = msg.channel();
+    check_channel(deps.as_ref(), channel)?;
+
+    let info = IbcChannelInfo {
+        channel_id: channel.endpoint.channel_id.clone(),
+        connection_id: channel.connection_id.clone(),
+        counterparty_port_id: channel.counterparty_endpoint.port_id.clone(),
+        counterparty_channel_id: channel.counterparty_endpoint.channel_id.clone(),
+    };
+    IBC_CHANNELS.save(deps.storage, &info.channel_id, &info)?;
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_connect")
+        .add_attribute("channel_id", info.channel_id))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_channel_close(
+    deps: DepsMut,
+    _env: Env,
+    msg: IbcChannelCloseMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    let channel_id = &msg.channel().endpoint.channel_id;
+    IBC_CHANNELS.remove(deps.storage, channel_id);
+    Ok(IbcBasicResponse::new()
+        .add_attribute("action", "ibc_channel_close")
+        .add_attribute("channel_id", channel_id))
+}
+
+/// Every packet is acknowledged, a failed verification with an `IbcAck::Error` rather than
+/// an error of the entry point, so nothing on the remote chain is left waiting for it
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_receive(
+    deps: DepsMut,
+    env: Env,
+    msg: IbcPacketReceiveMsg,
+) -> Result<IbcReceiveResponse, Never> {
+    let channel_id = msg.packet.dest.channel_id;
+    let res = match receive_packet(deps.as_ref(), &env, &channel_id, &msg.packet.data) {
+        Ok((result, events)) => {
+            let identifier = result.identifier.clone();
+            IbcReceiveResponse::new(ack_binary(IbcAck::Result(result)))
+                .add_attribute("identifier", identifier)
+                .add_attribute("success", "true")
+                .add_events(events)
+        }
+        Err(err) => IbcReceiveResponse::new(ack_binary(IbcAck::Error(err.to_string())))
+            .add_attribute("success", "false")
+            .add_attribute("error", err.to_string()),
+    };
+    Ok(res
+        .add_attribute("action", "ibc_verify_proof")
+        .add_attribute("channel_id", channel_id))
+}
+
+// The contract never sends packets, so acknowledgements and timeouts of its own packets
+// cannot arrive. Timeouts of verification packets are handled by the sending chain.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_ack(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketAckMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_ack"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn ibc_packet_timeout(
+    _deps: DepsMut,
+    _env: Env,
+    _msg: IbcPacketTimeoutMsg,
+) -> Result<IbcBasicResponse, ContractError> {
+    Ok(IbcBasicResponse::new().add_attribute("action", "ibc_packet_timeout"))
+}
+
+// an ack that fails to serialise is still an error ack
+fn ack_binary(ack: IbcAck) -> Binary {
+    to_json_binary(&ack)
+        .unwrap_or_else(|err| to_json_binary(&IbcAck::Error(err.to_string())).unwrap_or_default())
+}
+
+fn check_version(version: &str) -> Result<(), ContractError> {
+    if version != IBC_VERSION {
+        return Err(ContractError::InvalidIbcVersion {
+            expected: IBC_VERSION.to_string(),
+            version: version.to_string(),
+        });
+    }
+    Ok(())
+}
+
+// Channels are unordered, on the verification version and to a whitelisted peer
+fn check_channel(deps: Deps, channel: &IbcChannel) -> Result<(), ContractError> {
+    check_version(&channel.version)?;
+    if channel.order != IBC_ORDER {
+        return Err(ContractError::InvalidIbcOrder {});
+    }
+    let peer = (
+        channel.connection_id.as_str(),
+        channel.counterparty_endpoint.port_id.as_str(),
+    );
+    if !IBC_PEERS.has(deps.storage, peer) {
+        return Err(ContractError::IbcPeerNotAllowed {
+            connection_id: channel.connection_id.clone(),
+            port_id: channel.counterparty_endpoint.port_id.clone(),
+        });
+    }
+    Ok(())
+}
+
+fn receive_packet(
+    deps: Deps,
+    env: &Env,
+    channel_id: &str,
+    data: &Binary,
+) -> Result<(VerificationResult, Vec<Event>), ContractError> {
+    // the peer may have been disallowed while its channel was closing
+    let channel = IBC_CHANNELS
+        .may_load(deps.storage, channel_id)?
+        .filter(|channel| {
+            IBC_PEERS.has(
+                deps.storage,
+                (&channel.connection_id, &channel.counterparty_port_id),
+            )
+        })
+        .ok_or_else(|| ContractError::ChannelNotOpen {
+            channel_id: channel_id.to_string(),
+        })?;
+
+    let IbcPacketMsg::VerifyProof(ProofMsg { proof, domain, .. }) =
+        from_json(data).map_err(|err| ContractError::MalformedProof {
+            reason: err.to_string(),
+        })?;
+    let config = CONFIG.load(deps.storage)?;
+    if config.paused.verification {
+        return Err(ContractError::Paused {});
+    }
+    let epoch = check_claim(deps, &config, &proof, domain.as_deref())?;
+    let signers = check_witnesses(deps, &proof, epoch, env.block.time)?;
+
+    let context = proof.claimInfo.parse_context()?;
+    let claim = proof.signedClaim.claim;
+    let events = signers
+        .iter()
+        .map(|signer| {
+            Event::new("signer")
+                .add_attribute("sig", signer)
+                .add_attribute("channel_id", &channel.channel_id)
+        })
+        .collect();
+    let result = VerificationResult {
+        identifier: claim.identifier,
+        owner: claim.owner,
+        provider_hash: context.providerHash,
+        extracted_parameters: context.extractedParameters,
+        epoch: claim.epoch,
+        timestamp_s: claim.timestampS,
+        signers,
+    };
+    Ok((result, events))
+}
+
+// @dev - let a remote port open verification channels
+pub fn allow_ibc_peer(
+    deps: DepsMut,
+    peer: IbcPeer,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+
+    IBC_PEERS.save(
+        deps.storage,
+        (&peer.connection_id, &peer.port_id),
+        &Default::default(),
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "allow_ibc_peer")
+        .add_attribute("connection_id", peer.connection_id)
+        .add_attribute("port_id", peer.port_id))
+}
+
+// @dev - refuse a remote port and close the channels it opened
+pub fn disallow_ibc_peer(
+    deps: DepsMut,
+    peer: IbcPeer,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Check if sender is owner
+    if config.owner != sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if config.paused.admin {
+        return Err(ContractError::Paused {});
+    }
+
+    IBC_PEERS.remove(deps.storage, (&peer.connection_id, &peer.port_id));
+    let close = peer_channels(deps.as_ref(), &peer)?
+        .into_iter()
+        .map(|channel_id| IbcMsg::CloseChannel { channel_id });
+    Ok(Response::new()
+        .add_messages(close)
+        .add_attribute("action", "disallow_ibc_peer")
+        .add_attribute("connection_id", peer.connection_id)
+        .add_attribute("port_id", peer.port_id))
+}
+
+fn peer_channels(deps: Deps, peer: &IbcPeer) -> StdResult<Vec<String>> {
+    Ok(get_ibc_channels(deps.storage)?
+        .into_iter()
+        .filter(|channel| {
+            channel.connection_id == peer.connection_id
+                && channel.counterparty_port_id == peer.port_id
+        })
+        .map(|channel| channel.channel_id)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{execute, instantiate, query};
+    use crate::helpers::{ibc_verify_proof, parse_ibc_ack};
+    use crate::msg::{ExecuteMsg, GetIbcChannelsResponse, InstantiateMsg, QueryMsg};
+    use crate::state::EPOCHS;
+    use crate::testing;
+    use cosmwasm_std::testing::{
+        message_info, mock_dependencies, mock_env, mock_ibc_channel_close_confirm,
+        mock_ibc_channel_connect_ack, mock_ibc_channel_open_try, mock_ibc_packet_recv, MockApi,
+        MockQuerier, MockStorage,
+    };
+    use cosmwasm_std::{CosmosMsg, IbcAcknowledgement, IbcTimeout, OwnedDeps, Timestamp};
+
+    const CHANNEL: &str = "channel-0";
+
+    fn peer() -> IbcPeer {
+        // the counterparty of the cosmwasm-std mock channels
+        IbcPeer {
+            connection_id: "connection-2".to_string(),
+            port_id: "their_port".to_string(),
+        }
+    }
+
+    fn owner() -> Addr {
+        MockApi::default().addr_make("owner")
+    }
+
+    fn setup() -> OwnedDeps<MockStorage, MockApi, MockQuerier> {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: owner().to_string(),
+            badge: None,
+            guardian: None,
+        };
+        let info = message_info(&owner(), &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        deps
+    }
+
+    fn allow_peer(deps: DepsMut) {
+        let info = message_info(&owner(), &[]);
+        let msg = ExecuteMsg::AllowIbcPeer { peer: peer() };
+        execute(deps, mock_env(), info, msg).unwrap();
+    }
+
+    // Opens CHANNEL from the mock peer
+    fn connect(deps: DepsMut) {
+        let msg = mock_ibc_channel_connect_ack(CHANNEL, IBC_ORDER, IBC_VERSION);
+        ibc_channel_connect(deps, mock_env(), msg).unwrap();
+    }
+
+    fn receive(deps: DepsMut, proof: crate::claims::Proof) -> (IbcAck, IbcReceiveResponse) {
+        let timeout = IbcTimeout::with_timestamp(Timestamp::from_seconds(1));
+        let IbcMsg::SendPacket { data, .. } =
+            ibc_verify_proof(CHANNEL, proof, None, None, timeout).unwrap()
+        else {
+            unreachable!()
+        };
+        let mut msg = mock_ibc_packet_recv(CHANNEL, &()).unwrap();
+        msg.packet.data = data;
+        let res = ibc_packet_receive(deps, mock_env(), msg).unwrap();
+        let ack = IbcAcknowledgement::new(res.acknowledgement.clone().unwrap());
+        (parse_ibc_ack(&ack).unwrap(), res)
+    }
+
+    #[test]
+    fn only_whitelisted_peers_open_channels() {
+        let mut deps = setup();
+        let open = mock_ibc_channel_open_try(CHANNEL, IBC_ORDER, IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), open.clone()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::IbcPeerNotAllowed {
+                connection_id: peer().connection_id,
+                port_id: peer().port_id,
+            }
+        );
+
+        // only the owner changes the whitelist
+        let info = message_info(&MockApi::default().addr_make("user"), &[]);
+        let msg = ExecuteMsg::AllowIbcPeer { peer: peer() };
+        let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+        assert_eq!(err, ContractError::Unauthorized {});
+        allow_peer(deps.as_mut());
+        ibc_channel_open(deps.as_mut(), mock_env(), open).unwrap();
+
+        let wrong_version = mock_ibc_channel_open_try(CHANNEL, IBC_ORDER, "ics20-1");
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), wrong_version).unwrap_err();
+        assert!(matches!(err, ContractError::InvalidIbcVersion { .. }));
+        let ordered = mock_ibc_channel_open_try(CHANNEL, IbcOrder::Ordered, IBC_VERSION);
+        let err = ibc_channel_open(deps.as_mut(), mock_env(), ordered).unwrap_err();
+        assert_eq!(err, ContractError::InvalidIbcOrder {});
+
+        connect(deps.as_mut());
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetIbcChannels {}).unwrap();
+        let res: GetIbcChannelsResponse = from_json(res).unwrap();
+        assert_eq!(res.channels.len(), 1);
+        assert_eq!(res.channels[0].counterparty_port_id, peer().port_id);
+
+        let close = mock_ibc_channel_close_confirm(CHANNEL, IBC_ORDER, IBC_VERSION);
+        ibc_channel_close(deps.as_mut(), mock_env(), close).unwrap();
+        assert!(get_ibc_channels(&deps.storage).unwrap().is_empty());
+    }
+
+    #[test]
+    fn packets_are_acknowledged_with_the_verified_claim() {
+        let mut deps = setup();
+        allow_peer(deps.as_mut());
+        connect(deps.as_mut());
+        let witnesses = testing::test_witnesses(3);
+        let epoch = testing::test_epoch(1, &witnesses, 2);
+        EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
+
+        let proof = testing::test_proof(&epoch, &witnesses, mock_env().block.time);
+        let (ack, res) = receive(deps.as_mut(), proof.clone());
+        let IbcAck::Result(result) = ack else {
+            panic!("{:?}", ack);
+        };
+        assert_eq!(result.identifier, proof.signedClaim.claim.identifier);
+        assert_eq!(result.owner, testing::TEST_OWNER);
+        assert_eq!(result.provider_hash, testing::TEST_PROVIDER_HASH);
+        assert_eq!(result.extracted_parameters["username"], "reclaim");
+        assert_eq!(result.signers.len(), proof.signedClaim.signatures.len());
+        assert_eq!(res.events.len(), result.signers.len());
+
+        // a failed verification is an error ack, not a failed transaction
+        let mut forged = proof.clone();
+        forged.signedClaim.claim.epoch = 2;
+        let (ack, _) = receive(deps.as_mut(), forged);
+        assert_eq!(
+            ack,
+            IbcAck::Error("[10:epoch_not_found] Epoch 2 not found".to_string())
+        );
+
+        let msg = mock_ibc_packet_recv(CHANNEL, &"not a packet").unwrap();
+        let res = ibc_packet_receive(deps.as_mut(), mock_env(), msg).unwrap();
+        let ack = parse_ibc_ack(&IbcAcknowledgement::new(res.acknowledgement.unwrap())).unwrap();
+        assert!(matches!(ack, IbcAck::Error(err) if err.starts_with("[28:malformed_proof]")));
+    }
+
+    #[test]
+    fn disallowed_peers_are_disconnected() {
+        let mut deps = setup();
+        allow_peer(deps.as_mut());
+        connect(deps.as_mut());
+        let witnesses = testing::test_witnesses(1);
+        let epoch = testing::test_epoch(1, &witnesses, 1);
+        EPOCHS.save(deps.as_mut().storage, 1, &epoch).unwrap();
+        let proof = testing::test_proof(&epoch, &witnesses, mock_env().block.time);
+
+        let info = message_info(&owner(), &[]);
+        let msg = ExecuteMsg::DisallowIbcPeer { peer: peer() };
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Ibc(IbcMsg::CloseChannel {
+                channel_id: CHANNEL.to_string()
+            })
+        );
+
+        // packets relayed before the channel closes are refused
+        let (ack, _) = receive(deps.as_mut(), proof);
+        assert_eq!(
+            ack,
+            IbcAck::Error(
+                "[34:channel_not_open] Channel channel-0 is not an open verification channel"
+                    .to_string()
+            )
+        );
+    }
+}
@@ -2,7 +2,10 @@ pub mod claims;
 pub mod contract;
 mod error;
 pub mod helpers;
+pub mod ibc;
 pub mod msg;
 pub mod state;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 
-pub use crate::error::ContractError;
+pub use crate::error::{error_codes_json, ContractError, ERROR_CODES};
@@ -1,33 +1,292 @@
-use cosmwasm_std::Uint128;
+use cosmwasm_schema::QueryResponses;
+use std::collections::BTreeMap;
+
+use cosmwasm_std::{Addr, Coin, Decimal, Uint128};
+use cw_utils::Expiration;
 use schemars::JsonSchema;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::{
     claims::Proof,
-    state::{Epoch, Witness},
+    state::{
+        Badge, BadgeConfig, BadgeExtension, Domain, DomainPolicy, Epoch, EpochPolicy,
+        IbcChannelInfo, IbcPeer, PauseScope, PendingEpochChange, Role, SizeLimits, Witness,
+    },
 };
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct InstantiateMsg {
     pub owner: String,
+    // mint a soulbound badge for every verified claim when set
+    #[serde(default)]
+    pub badge: Option<BadgeConfig>,
+    #[serde(default)]
+    pub guardian: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     VerifyProof(ProofMsg),
     AddEpoch {
         witness: Vec<Witness>,
         minimum_witness: Uint128,
+        #[serde(default)]
+        policy: EpochPolicy,
+        #[serde(default)]
+        domain: Option<String>,
+    },
+    UpdateWitnesses {
+        witness: Vec<Witness>,
+        minimum_witness: Uint128,
+        #[serde(default)]
+        policy: EpochPolicy,
+        #[serde(default)]
+        domain: Option<String>,
+    },
+    CreateDomain {
+        name: String,
+        admin: String,
+        #[serde(default)]
+        policy: DomainPolicy,
+    },
+    /// Fields left unset keep their current value
+    UpdateDomain {
+        name: String,
+        admin: Option<String>,
+        policy: Option<DomainPolicy>,
+    },
+    SetEpochDelay {
+        seconds: u64,
+    },
+    ExecuteEpochChange {
+        id: u64,
+    },
+    CancelEpochChange {
+        id: u64,
+    },
+    UpdateBadgeConfig {
+        badge: Option<BadgeConfig>,
+    },
+    /// Fields left unset keep their current value, `null` clears them
+    UpdateFeeConfig {
+        #[serde(
+            default,
+            deserialize_with = "deserialize_some",
+            skip_serializing_if = "Option::is_none"
+        )]
+        fee: Option<Option<Coin>>,
+        #[serde(
+            default,
+            deserialize_with = "deserialize_some",
+            skip_serializing_if = "Option::is_none"
+        )]
+        treasury: Option<Option<String>>,
+        /// Part of each fee shared among the signing witnesses, `null` shares none of it
+        #[serde(
+            default,
+            deserialize_with = "deserialize_some",
+            skip_serializing_if = "Option::is_none"
+        )]
+        witness_share: Option<Option<Decimal>>,
+    },
+    SetProviderFee {
+        provider_hash: String,
+        fee: Option<Coin>,
+    },
+    WithdrawFees {},
+    /// Sets the sender as the payout address of `witness`, once. The signature is the witness
+    /// key signing `"reclaim-xion payout\n{contract address}\n{payout address}"` with the scheme
+    /// of its record in the current epoch of `domain`, a personal signature for witnesses not in it.
+    RegisterWitnessPayout {
+        witness: String,
+        signature: String,
+        #[serde(default)]
+        domain: Option<String>,
+    },
+    ClaimWitnessRewards {
+        witness: String,
+    },
+    Pause {
+        scope: PauseScope,
+    },
+    Unpause {
+        scope: PauseScope,
+    },
+    UpdateGuardian {
+        guardian: Option<String>,
+    },
+    /// Granting `Owner` transfers ownership to `address`
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+    UpdateLimits {
+        limits: SizeLimits,
+    },
+    /// Lets `peer` open verification channels to the contract
+    AllowIbcPeer {
+        peer: IbcPeer,
+    },
+    /// Closes the open channels of `peer` and refuses new ones
+    DisallowIbcPeer {
+        peer: IbcPeer,
     },
 }
 
+/// Messages chain governance can send through the sudo entry point. They bypass the
+/// timelock and the admin pause, so a proposal can recover the verifier if admin keys are lost.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    UpdateOwner {
+        owner: String,
+    },
+    AddEpoch {
+        witness: Vec<Witness>,
+        minimum_witness: Uint128,
+        #[serde(default)]
+        policy: EpochPolicy,
+        // the default domain when unset
+        #[serde(default)]
+        domain: Option<String>,
+    },
+    EndEpoch {
+        id: u128,
+        // the default domain when unset
+        #[serde(default)]
+        domain: Option<String>,
+    },
+    Pause {
+        scope: PauseScope,
+    },
+    Unpause {
+        scope: PauseScope,
+    },
+    /// Fields left unset keep their current value, `null` clears the optional ones
+    UpdateConfig {
+        #[serde(
+            default,
+            deserialize_with = "deserialize_some",
+            skip_serializing_if = "Option::is_none"
+        )]
+        guardian: Option<Option<String>>,
+        epoch_delay: Option<u64>,
+        #[serde(
+            default,
+            deserialize_with = "deserialize_some",
+            skip_serializing_if = "Option::is_none"
+        )]
+        fee: Option<Option<Coin>>,
+        #[serde(
+            default,
+            deserialize_with = "deserialize_some",
+            skip_serializing_if = "Option::is_none"
+        )]
+        treasury: Option<Option<String>>,
+        witness_share: Option<Decimal>,
+        #[serde(
+            default,
+            deserialize_with = "deserialize_some",
+            skip_serializing_if = "Option::is_none"
+        )]
+        badge: Option<Option<BadgeConfig>>,
+        limits: Option<SizeLimits>,
+    },
+}
+
+// A present field, `null` included, is `Some`, so only a missing one reads as `None`
+fn deserialize_some<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    T::deserialize(deserializer).map(Some)
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, QueryResponses)]
+#[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
-    GetAllEpoch {},
-    GetEpoch { id: u128 },
+    #[returns(GetAllEpochResponse)]
+    GetAllEpoch {
+        #[serde(default)]
+        domain: Option<String>,
+    },
+    #[returns(GetEpochResponse)]
+    GetEpoch {
+        id: u128,
+        #[serde(default)]
+        domain: Option<String>,
+    },
+    #[returns(GetDomainResponse)]
+    GetDomain { name: String },
+    #[returns(GetAllDomainsResponse)]
+    GetAllDomains {},
+    #[returns(GetBadgeResponse)]
+    GetBadge { token_id: String },
+    /// cw721: the holder of a badge. Badges are soulbound, so there are never approvals.
+    #[returns(OwnerOfResponse)]
+    OwnerOf {
+        token_id: String,
+        #[serde(default)]
+        include_expired: Option<bool>,
+    },
+    /// cw721: the metadata of a badge
+    #[returns(NftInfoResponse)]
+    NftInfo { token_id: String },
+    /// cw721: `owner_of` and `nft_info` together
+    #[returns(AllNftInfoResponse)]
+    AllNftInfo {
+        token_id: String,
+        #[serde(default)]
+        include_expired: Option<bool>,
+    },
+    /// cw721: the badges held by `owner`, in token id order
+    #[returns(TokensResponse)]
+    Tokens {
+        owner: String,
+        #[serde(default)]
+        start_after: Option<String>,
+        #[serde(default)]
+        limit: Option<u32>,
+    },
+    /// cw721: every badge, in token id order
+    #[returns(TokensResponse)]
+    AllTokens {
+        #[serde(default)]
+        start_after: Option<String>,
+        #[serde(default)]
+        limit: Option<u32>,
+    },
+    /// cw721: the number of badges minted
+    #[returns(NumTokensResponse)]
+    NumTokens {},
+    /// cw721: the name and symbol of the badge collection
+    #[returns(ContractInfoResponse)]
+    ContractInfo {},
+    #[returns(GetFeeConfigResponse)]
+    GetFeeConfig {},
+    #[returns(GetProviderFeeResponse)]
+    GetProviderFee { provider_hash: String },
+    #[returns(GetWitnessRewardsResponse)]
+    GetWitnessRewards { witness: String },
+    #[returns(GetPauseStateResponse)]
+    GetPauseState {},
+    #[returns(GetRoleMembersResponse)]
+    GetRoleMembers { role: Role },
+    #[returns(GetPendingEpochChangesResponse)]
+    GetPendingEpochChanges {},
+    #[returns(GetLimitsResponse)]
+    GetLimits {},
+    #[returns(GetIbcPeersResponse)]
+    GetIbcPeers {},
+    #[returns(GetIbcChannelsResponse)]
+    GetIbcChannels {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -42,8 +301,175 @@ pub struct GetEpochResponse {
     pub epoch: Epoch,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetDomainResponse {
+    pub domain: Domain,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetAllDomainsResponse {
+    pub names: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetBadgeResponse {
+    pub badge: Badge,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OwnerOfResponse {
+    pub owner: String,
+    pub approvals: Vec<Approval>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Approval {
+    pub spender: String,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct NftInfoResponse {
+    pub token_uri: Option<String>,
+    pub extension: BadgeExtension,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct AllNftInfoResponse {
+    pub access: OwnerOfResponse,
+    pub info: NftInfoResponse,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct NumTokensResponse {
+    pub count: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ContractInfoResponse {
+    pub name: String,
+    pub symbol: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetFeeConfigResponse {
+    pub fee: Option<Coin>,
+    pub treasury: Addr,
+    pub witness_share: Decimal,
+    pub collected: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetProviderFeeResponse {
+    // the fee charged for claims of this provider, after falling back to the default
+    pub fee: Option<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetWitnessRewardsResponse {
+    pub payout: Option<Addr>,
+    pub rewards: Vec<Coin>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetPauseStateResponse {
+    pub verification: bool,
+    pub admin: bool,
+    pub guardian: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetRoleMembersResponse {
+    pub members: Vec<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetPendingEpochChangesResponse {
+    pub delay: u64,
+    pub changes: Vec<PendingEpochChange>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetLimitsResponse {
+    pub limits: SizeLimits,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetIbcPeersResponse {
+    pub peers: Vec<IbcPeer>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct GetIbcChannelsResponse {
+    pub channels: Vec<IbcChannelInfo>,
+}
+
+/// Data of a packet asking the contract to verify a proof, sent by a remote chain over an
+/// open verification channel
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcPacketMsg {
+    VerifyProof(ProofMsg),
+}
+
+/// Acknowledgement of an `IbcPacketMsg`, the verified claim or the `[code:kind]` error
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum IbcAck {
+    Result(VerificationResult),
+    Error(String),
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct VerificationResult {
+    pub identifier: String,
+    pub owner: String,
+    pub provider_hash: String,
+    pub extracted_parameters: BTreeMap<String, String>,
+    pub epoch: u64,
+    pub timestamp_s: u64,
+    // witnesses whose signatures counted towards the threshold
+    pub signers: Vec<String>,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ProofMsg {
+    // the contract format, or the proof the Reclaim JS SDK emits
+    #[serde(deserialize_with = "crate::claims::deserialize_proof")]
+    #[schemars(with = "crate::claims::ProofFormat")]
     pub proof: Proof,
+    // the default domain when unset
+    #[serde(default)]
+    pub domain: Option<String>,
+    /// Personal signature of `"reclaim-xion badge\n{contract address}\n{sender address}"` by
+    /// the claim owner key, making the sender the badge holder. Only read when badges are
+    /// enabled and the claim owner is not an address on this chain.
+    #[serde(default)]
+    pub owner_signature: Option<String>,
 }
@@ -1,36 +1,280 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Coin, Decimal, Empty, Uint128};
 use cosmwasm_std::{Order, StdResult, Storage};
-use cw_storage_plus::{Item, Map};
+use cw_storage_plus::{Bound, Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+pub use reclaim_verifier_core::{SignatureScheme, Witness};
+
 pub const EPOCHS: Map<u128, Epoch> = Map::new("epochs");
+// named verifier domains, each with its own epoch sequence in DOMAIN_EPOCHS
+pub const DOMAINS: Map<&str, Domain> = Map::new("domains");
+pub const DOMAIN_EPOCHS: Map<(&str, u128), Epoch> = Map::new("domain_epochs");
 pub const CONFIG: Item<Config> = Item::new("config");
+pub const BADGES: Map<&str, Badge> = Map::new("badges");
+// secondary index of badges by holder, so they can be listed per owner
+pub const BADGES_BY_OWNER: Map<(&Addr, &str), Empty> = Map::new("badges_by_owner");
+pub const BADGE_COUNT: Item<u64> = Item::new("badge_count");
+// fee overrides keyed by the provider hash of the claim context
+pub const PROVIDER_FEES: Map<&str, Coin> = Map::new("provider_fees");
+// fees held by the contract until withdrawn to the treasury, keyed by denom
+pub const COLLECTED_FEES: Map<&str, Uint128> = Map::new("collected_fees");
+// fee shares claimable by witnesses, keyed by (witness address, denom)
+pub const WITNESS_REWARDS: Map<(&str, &str), Uint128> = Map::new("witness_rewards");
+// XION address each witness has registered to receive its rewards
+pub const WITNESS_PAYOUTS: Map<&str, Addr> = Map::new("witness_payouts");
+// delegated admin roles, keyed by (role, holder)
+pub const ROLES: Map<(&str, &Addr), Empty> = Map::new("roles");
+pub const PENDING_EPOCH_CHANGES: Map<u64, PendingEpochChange> = Map::new("pending_epoch_changes");
+pub const LAST_PENDING_ID: Item<u64> = Item::new("last_pending_id");
+// remote ports allowed to open verification channels, keyed by (connection id, port id)
+pub const IBC_PEERS: Map<(&str, &str), Empty> = Map::new("ibc_peers");
+// open verification channels, keyed by the channel id on this chain
+pub const IBC_CHANNELS: Map<&str, IbcChannelInfo> = Map::new("ibc_channels");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub struct Config {
     pub owner: Addr,
     pub current_epoch: Uint128,
+    // badge minting is disabled when unset
+    #[serde(default)]
+    pub badge: Option<BadgeConfig>,
+    // verification is free when unset, unless a provider fee applies
+    #[serde(default)]
+    pub fee: Option<Coin>,
+    // collected fees are withdrawn to the owner when unset
+    #[serde(default)]
+    pub treasury: Option<Addr>,
+    // portion of every fee split between the witnesses that signed the claim
+    #[serde(default)]
+    pub witness_fee_share: Decimal,
+    #[serde(default)]
+    pub paused: PauseState,
+    // can pause and unpause alongside the owner
+    #[serde(default)]
+    pub guardian: Option<Addr>,
+    // seconds an epoch change waits in the pending queue, applied immediately when zero
+    #[serde(default)]
+    pub epoch_delay: u64,
+    #[serde(default)]
+    pub limits: SizeLimits,
+}
+
+/// Largest inputs the contract accepts. Proofs are checked before the claim is hashed or any
+/// signature recovered. Claim text over `MAX_CLAIM_TEXT_BYTES` is rejected earlier, while the
+/// message is parsed, so the byte limits cannot be set above it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SizeLimits {
+    /// Bytes of the claim `parameters`
+    pub max_parameters_bytes: u32,
+    /// Bytes of the claim `context`
+    pub max_context_bytes: u32,
+    /// Signatures on a claim
+    pub max_signatures: u32,
+    /// Witnesses in an epoch
+    pub max_witnesses: u32,
+}
+
+impl Default for SizeLimits {
+    fn default() -> Self {
+        SizeLimits {
+            max_parameters_bytes: 16 * 1024,
+            max_context_bytes: 8 * 1024,
+            max_signatures: 32,
+            max_witnesses: 64,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PauseState {
+    /// `verify_proof` is rejected
+    pub verification: bool,
+    /// epoch management and configuration changes are rejected
+    pub admin: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum EpochChange {
+    AddEpoch {
+        witness: Vec<Witness>,
+        minimum_witness: Uint128,
+        #[serde(default)]
+        policy: EpochPolicy,
+    },
+    /// Replaces the witness set of `epoch_id`, the current epoch when the change was made
+    UpdateWitnesses {
+        epoch_id: Uint128,
+        witness: Vec<Witness>,
+        minimum_witness: Uint128,
+        #[serde(default)]
+        policy: EpochPolicy,
+    },
+    SetEpochDelay {
+        seconds: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct PendingEpochChange {
+    pub id: u64,
+    // the default domain when unset
+    #[serde(default)]
+    pub domain: Option<String>,
+    pub change: EpochChange,
+    pub proposer: Addr,
+    pub executable_after: u64,
+}
+
+/// An independent verifier with its own admin, epochs and policy. Proofs and epoch
+/// messages that name no domain use the default domain held in `Config` and `EPOCHS`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Domain {
+    pub name: String,
+    pub admin: Addr,
+    pub current_epoch: Uint128,
+    pub policy: DomainPolicy,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DomainPolicy {
+    /// Provider hashes the domain accepts proofs for, any provider when empty
+    pub allowed_providers: Vec<String>,
+}
+
+/// A port on a remote chain, reached through an IBC connection of this chain
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct IbcPeer {
+    pub connection_id: String,
+    pub port_id: String,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct Witness {
-    pub address: String,
-    pub host: String,
+pub struct IbcChannelInfo {
+    pub channel_id: String,
+    pub connection_id: String,
+    pub counterparty_port_id: String,
+    pub counterparty_channel_id: String,
 }
 
-impl Witness {
-    pub fn get_addresses(witness: Vec<Witness>) -> Vec<String> {
-        let mut vec_addresses = vec![];
-        for wit in witness {
-            vec_addresses.push(wit.address);
+/// Admin roles. The owner is `Config::owner` and implicitly holds every other role.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Owner,
+    EpochManager,
+    WitnessManager,
+    Pauser,
+    FeeManager,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::EpochManager => "epoch_manager",
+            Role::WitnessManager => "witness_manager",
+            Role::Pauser => "pauser",
+            Role::FeeManager => "fee_manager",
         }
-        return vec_addresses;
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseScope {
+    Verification,
+    Admin,
+    All,
+}
+
+impl PauseState {
+    pub fn set(&mut self, scope: PauseScope, paused: bool) {
+        match scope {
+            PauseScope::Verification => self.verification = paused,
+            PauseScope::Admin => self.admin = paused,
+            PauseScope::All => {
+                self.verification = paused;
+                self.admin = paused;
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BadgeConfig {
+    /// Keys of the claim context `extractedParameters` copied into the badge metadata
+    pub extracted_parameters: Vec<String>,
+}
+
+/// Soulbound credential minted by `verify_proof` when badges are enabled.
+/// The token id is the claim identifier, and there is no message to transfer or burn it.
+/// Badges answer the cw721 queries, with `BadgeExtension` as their metadata.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct Badge {
+    pub token_id: String,
+    pub owner: Addr,
+    pub provider_hash: String,
+    pub extracted_parameters: Vec<BadgeAttribute>,
+    pub minted_at: u64,
+}
+
+/// The `extension` of a badge in the cw721 `nft_info` query
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BadgeExtension {
+    pub provider_hash: String,
+    pub extracted_parameters: Vec<BadgeAttribute>,
+    pub minted_at: u64,
+}
+
+impl From<Badge> for BadgeExtension {
+    fn from(badge: Badge) -> Self {
+        BadgeExtension {
+            provider_hash: badge.provider_hash,
+            extracted_parameters: badge.extracted_parameters,
+            minted_at: badge.minted_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BadgeAttribute {
+    pub key: String,
+    pub value: String,
+}
+
+/// How many of the witnesses selected for a claim have to sign it
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct EpochPolicy {
+    /// Distinct selected witnesses that must sign, all of them when unset. A claim that drew
+    /// fewer distinct witnesses needs all of those.
+    pub threshold: Option<Uint128>,
+    pub unknown_signers: UnknownSignerPolicy,
+}
+
+/// What to do with signatures from witnesses that were not selected for the claim
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum UnknownSignerPolicy {
+    #[default]
+    Reject,
+    Ignore,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct Epoch {
@@ -39,8 +283,136 @@ pub struct Epoch {
     pub timestamp_end: u64,
     pub minimum_witness_for_claim_creation: Uint128,
     pub witness: Vec<Witness>,
+    // set by governance, proofs of an ended epoch are rejected
+    #[serde(default)]
+    pub ended: bool,
+    #[serde(default)]
+    pub policy: EpochPolicy,
+}
+
+pub fn get_all_epochs(storage: &dyn Storage, domain: Option<&str>) -> StdResult<Vec<u128>> {
+    match domain {
+        None => EPOCHS.keys(storage, None, None, Order::Ascending).collect(),
+        Some(domain) => DOMAIN_EPOCHS
+            .prefix(domain)
+            .keys(storage, None, None, Order::Ascending)
+            .collect(),
+    }
+}
+
+pub fn load_epoch(storage: &dyn Storage, domain: Option<&str>, id: u128) -> StdResult<Epoch> {
+    match domain {
+        None => EPOCHS.load(storage, id),
+        Some(domain) => DOMAIN_EPOCHS.load(storage, (domain, id)),
+    }
+}
+
+pub fn may_load_epoch(
+    storage: &dyn Storage,
+    domain: Option<&str>,
+    id: u128,
+) -> StdResult<Option<Epoch>> {
+    match domain {
+        None => EPOCHS.may_load(storage, id),
+        Some(domain) => DOMAIN_EPOCHS.may_load(storage, (domain, id)),
+    }
+}
+
+pub fn save_epoch(storage: &mut dyn Storage, domain: Option<&str>, epoch: &Epoch) -> StdResult<()> {
+    match domain {
+        None => EPOCHS.save(storage, epoch.id.u128(), epoch),
+        Some(domain) => DOMAIN_EPOCHS.save(storage, (domain, epoch.id.u128()), epoch),
+    }
+}
+
+pub fn get_all_domains(storage: &dyn Storage) -> StdResult<Vec<String>> {
+    DOMAINS
+        .keys(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+pub fn get_badges_by_owner(
+    storage: &dyn Storage,
+    owner: &Addr,
+    start_after: Option<&str>,
+    limit: usize,
+) -> StdResult<Vec<String>> {
+    BADGES_BY_OWNER
+        .prefix(owner)
+        .keys(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect()
+}
+
+pub fn get_all_badges(
+    storage: &dyn Storage,
+    start_after: Option<&str>,
+    limit: usize,
+) -> StdResult<Vec<String>> {
+    BADGES
+        .keys(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect()
+}
+
+pub fn get_collected_fees(storage: &dyn Storage) -> StdResult<Vec<Coin>> {
+    COLLECTED_FEES
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin::new(amount, denom)))
+        .collect()
+}
+
+pub fn get_witness_rewards(storage: &dyn Storage, witness: &str) -> StdResult<Vec<Coin>> {
+    WITNESS_REWARDS
+        .prefix(witness)
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin::new(amount, denom)))
+        .collect()
+}
+
+pub fn has_role(storage: &dyn Storage, config: &Config, role: Role, addr: &Addr) -> bool {
+    config.owner == addr || ROLES.has(storage, (role.as_str(), addr))
+}
+
+pub fn get_role_members(storage: &dyn Storage, role: Role) -> StdResult<Vec<Addr>> {
+    ROLES
+        .prefix(role.as_str())
+        .keys(storage, None, None, Order::Ascending)
+        .collect()
+}
+
+pub fn get_ibc_peers(storage: &dyn Storage) -> StdResult<Vec<IbcPeer>> {
+    IBC_PEERS
+        .keys(storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(connection_id, port_id)| IbcPeer {
+                connection_id,
+                port_id,
+            })
+        })
+        .collect()
+}
+
+pub fn get_ibc_channels(storage: &dyn Storage) -> StdResult<Vec<IbcChannelInfo>> {
+    IBC_CHANNELS
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, channel)| channel))
+        .collect()
 }
 
-pub fn get_all_epochs(storage: &dyn Storage) -> StdResult<Vec<u128>> {
-    EPOCHS.keys(storage, None, None, Order::Ascending).collect()
+pub fn get_pending_epoch_changes(storage: &dyn Storage) -> StdResult<Vec<PendingEpochChange>> {
+    PENDING_EPOCH_CHANGES
+        .range(storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, pending)| pending))
+        .collect()
 }
@@ -0,0 +1,234 @@
+//! Test support for the contract and its integrations, behind the `testing` feature.
+//!
+//! Witness keys are derived from a seed and signatures are deterministic, so the proofs
+//! built here are the same on every run. They are signed the way Reclaim attestors sign,
+//! an Ethereum personal signature over `CompleteClaimData::serialise`, unless the witness
+//! is made with one of the keyed constructors.
+
+use cosmwasm_std::{Timestamp, Uint128};
+use k256::ecdsa::signature::hazmat::PrehashSigner;
+use k256::ecdsa::{Signature, SigningKey};
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+use crate::claims::{append_0x, keccak256, ClaimInfo, CompleteClaimData, Proof, SignedClaim};
+use crate::contract::fetch_witness_for_claim;
+use crate::state::{Epoch, EpochPolicy, SignatureScheme, Witness};
+
+pub const TEST_PROVIDER_HASH: &str =
+    "0x0000000000000000000000000000000000000000000000000000000000000001";
+pub const TEST_OWNER: &str = "0x0000000000000000000000000000000000000001";
+
+/// A witness with its signing key
+#[derive(Clone, Debug)]
+pub struct TestWitness {
+    key: TestKey,
+    pub host: String,
+}
+
+#[derive(Clone, Debug)]
+enum TestKey {
+    Eth(SigningKey),
+    Secp256k1(SigningKey),
+    Ed25519(ed25519_zebra::SigningKey),
+}
+
+fn secret(seed: u32) -> [u8; 32] {
+    let mut secret = [0u8; 32];
+    secret[28..].copy_from_slice(&seed.to_be_bytes());
+    secret
+}
+
+fn secp256k1_key(seed: u32) -> SigningKey {
+    SigningKey::from_slice(&secret(seed)).expect("seed must be non-zero")
+}
+
+impl TestWitness {
+    /// The witness for `seed`, which has to be non-zero
+    pub fn new(seed: u32) -> Self {
+        Self::with_key(seed, TestKey::Eth(secp256k1_key(seed)))
+    }
+
+    /// A `SignatureScheme::Secp256k1` witness for `seed`, which has to be non-zero
+    pub fn secp256k1(seed: u32) -> Self {
+        Self::with_key(seed, TestKey::Secp256k1(secp256k1_key(seed)))
+    }
+
+    /// A `SignatureScheme::Ed25519` witness for `seed`
+    pub fn ed25519(seed: u32) -> Self {
+        Self::with_key(seed, TestKey::Ed25519(secret(seed).into()))
+    }
+
+    fn with_key(seed: u32, key: TestKey) -> Self {
+        TestWitness {
+            key,
+            host: format!("https://witness-{seed}.reclaim.test"),
+        }
+    }
+
+    pub fn address(&self) -> String {
+        self.witness().address
+    }
+
+    /// The epoch record of the witness, keyed ones going by the address of their key
+    pub fn witness(&self) -> Witness {
+        let (scheme, public_key) = match &self.key {
+            TestKey::Eth(_) => (SignatureScheme::EthPersonalSign, None),
+            TestKey::Secp256k1(key) => {
+                let point = key.verifying_key().to_encoded_point(true);
+                (SignatureScheme::Secp256k1, Some(hex::encode(point)))
+            }
+            TestKey::Ed25519(key) => {
+                let public_key = ed25519_zebra::VerificationKey::from(key);
+                (SignatureScheme::Ed25519, Some(hex::encode(public_key)))
+            }
+        };
+        let mut witness = Witness {
+            address: String::new(),
+            host: self.host.clone(),
+            scheme,
+            public_key,
+        };
+        witness.address = match &self.key {
+            TestKey::Eth(key) => {
+                let point = key.verifying_key().to_encoded_point(false);
+                let hash = Keccak256::new_with_prefix(&point.as_bytes()[1..]).finalize();
+                append_0x(&hex::encode(&hash[12..]))
+            }
+            _ => witness.key_address().unwrap(),
+        };
+        witness
+    }
+
+    /// Signs the claim as a Reclaim attestor does
+    pub fn sign(&self, claim: &CompleteClaimData) -> String {
+        self.sign_message(&claim.serialise())
+    }
+
+    /// Signs `message` with the scheme of the witness, as for `payout_registration_message`.
+    /// Eth witnesses personal-sign it, as for `badge_owner_message`
+    pub fn sign_message(&self, message: &str) -> String {
+        let bytes = match &self.key {
+            TestKey::Eth(key) => {
+                let message = keccak256(message);
+                let (signature, recovery_id) = key.sign_prehash_recoverable(&message).unwrap();
+                let mut bytes = signature.to_bytes().to_vec();
+                bytes.push(27 + recovery_id.to_byte());
+                bytes
+            }
+            TestKey::Secp256k1(key) => {
+                let hash = Sha256::digest(message.as_bytes());
+                let signature: Signature = key.sign_prehash(&hash).unwrap();
+                signature.to_bytes().to_vec()
+            }
+            TestKey::Ed25519(key) => key.sign(message.as_bytes()).to_bytes().to_vec(),
+        };
+        append_0x(&hex::encode(bytes))
+    }
+}
+
+/// `count` witnesses with the seeds `1..=count`
+pub fn test_witnesses(count: u32) -> Vec<TestWitness> {
+    (1..=count).map(TestWitness::new).collect()
+}
+
+/// A signature of the right length that no key can have made
+pub fn malformed_signature() -> String {
+    let mut bytes = [0u8; 65];
+    bytes[64] = 27;
+    append_0x(&hex::encode(bytes))
+}
+
+pub fn test_claim_info() -> ClaimInfo {
+    ClaimInfo {
+        provider: "http".to_string(),
+        parameters: r#"{"method":"GET","url":"https://example.com"}"#.to_string(),
+        context: format!(
+            r#"{{"extractedParameters":{{"username":"reclaim"}},"providerHash":"{}"}}"#,
+            TEST_PROVIDER_HASH
+        ),
+    }
+}
+
+/// The claim of `claim_info` for `TEST_OWNER`
+pub fn test_claim(claim_info: &ClaimInfo, epoch: u64, timestamp_s: u64) -> CompleteClaimData {
+    CompleteClaimData {
+        identifier: claim_info.hash(),
+        owner: TEST_OWNER.to_string(),
+        epoch,
+        timestampS: timestamp_s,
+    }
+}
+
+/// An open epoch of `witnesses`, with the default policy
+pub fn test_epoch(id: u128, witnesses: &[TestWitness], minimum_witness: u128) -> Epoch {
+    Epoch {
+        id: Uint128::new(id),
+        timestamp_start: 0,
+        timestamp_end: 0,
+        minimum_witness_for_claim_creation: Uint128::new(minimum_witness),
+        witness: witnesses.iter().map(TestWitness::witness).collect(),
+        ended: false,
+        policy: EpochPolicy::default(),
+    }
+}
+
+/// The proof of `claim` signed by `signers`, in order
+pub fn sign_proof(
+    claim_info: ClaimInfo,
+    claim: CompleteClaimData,
+    signers: &[&TestWitness],
+) -> Proof {
+    let signatures = signers.iter().map(|signer| signer.sign(&claim)).collect();
+    Proof {
+        claimInfo: claim_info,
+        signedClaim: SignedClaim { claim, signatures },
+    }
+}
+
+/// The distinct `witnesses` the contract selects from `epoch` for the claim `identifier`
+/// when verified at `time`
+pub fn selected_witnesses<'a>(
+    epoch: &Epoch,
+    witnesses: &'a [TestWitness],
+    identifier: &str,
+    time: Timestamp,
+) -> Vec<&'a TestWitness> {
+    let mut selected = Witness::get_addresses(fetch_witness_for_claim(
+        epoch.clone(),
+        identifier.to_string(),
+        time,
+    ));
+    // a witness selected more than once only signs once
+    let mut seen = vec![];
+    selected.retain(|address| {
+        let first = !seen.contains(address);
+        seen.push(address.clone());
+        first
+    });
+    selected
+        .iter()
+        .filter_map(|address| witnesses.iter().find(|w| w.address() == *address))
+        .collect()
+}
+
+/// A proof of `test_claim_info` in `epoch`, signed once by every witness the contract selects
+/// for it when verified at `time`
+pub fn test_proof(epoch: &Epoch, witnesses: &[TestWitness], time: Timestamp) -> Proof {
+    owned_test_proof(epoch, witnesses, time, TEST_OWNER)
+}
+
+/// `test_proof` of a claim by `owner`, which receives the badge when it is an address on
+/// the chain
+pub fn owned_test_proof(
+    epoch: &Epoch,
+    witnesses: &[TestWitness],
+    time: Timestamp,
+    owner: &str,
+) -> Proof {
+    let claim_info = test_claim_info();
+    let mut claim = test_claim(&claim_info, epoch.id.u128() as u64, time.seconds());
+    claim.owner = owner.to_string();
+    let signers = selected_witnesses(epoch, witnesses, &claim.identifier, time);
+    sign_proof(claim_info, claim, &signers)
+}
@@ -0,0 +1,332 @@
+//! End to end tests of the contract in a cw-multi-test `App`, driving it through epoch
+//! creation, rotation and verification, directly and from another contract.
+
+use cosmwasm_std::{
+    to_json_binary, Addr, Binary, Deps, DepsMut, Empty, Env, MessageInfo, Reply, Response,
+    StdError, StdResult, SubMsg, Uint128,
+};
+use cw_multi_test::{App, AppResponse, ContractWrapper, Executor};
+use serde::{Deserialize, Serialize};
+
+use reclaim_xion::claims::Proof;
+use reclaim_xion::contract::{execute, instantiate, query, sudo};
+use reclaim_xion::helpers::ReclaimVerifierContract;
+use reclaim_xion::msg::{InstantiateMsg, SudoMsg};
+use reclaim_xion::state::{BadgeConfig, EpochPolicy, PauseScope};
+use reclaim_xion::testing::{
+    owned_test_proof, test_epoch, test_proof, test_witnesses, TestWitness,
+};
+
+// A contract that verifies proofs on behalf of its users, and returns the signers as data
+mod caller {
+    use super::*;
+
+    pub const VERIFY_REPLY: u64 = 1;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+    #[serde(rename_all = "snake_case")]
+    pub enum ExecuteMsg {
+        Verify { verifier: String, proof: Proof },
+    }
+
+    pub fn instantiate(
+        _deps: DepsMut,
+        _env: Env,
+        _info: MessageInfo,
+        _msg: Empty,
+    ) -> StdResult<Response> {
+        Ok(Response::new())
+    }
+
+    pub fn execute(
+        deps: DepsMut,
+        _env: Env,
+        info: MessageInfo,
+        msg: ExecuteMsg,
+    ) -> StdResult<Response> {
+        let ExecuteMsg::Verify { verifier, proof } = msg;
+        let verifier = ReclaimVerifierContract(deps.api.addr_validate(&verifier)?);
+        let verify = verifier.verify_proof(proof, None, None, info.funds)?;
+        Ok(Response::new().add_submessage(SubMsg::reply_on_success(verify, VERIFY_REPLY)))
+    }
+
+    pub fn reply(_deps: DepsMut, _env: Env, reply: Reply) -> StdResult<Response> {
+        let result = reply.result.into_result().map_err(StdError::generic_err)?;
+        let signers: Vec<String> = result
+            .events
+            .iter()
+            .filter(|event| event.ty == "wasm-signer")
+            .flat_map(|event| &event.attributes)
+            .filter(|attribute| attribute.key == "sig")
+            .map(|attribute| attribute.value.clone())
+            .collect();
+        Ok(Response::new().set_data(to_json_binary(&signers)?))
+    }
+
+    pub fn query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+        Err(StdError::generic_err("no queries"))
+    }
+}
+
+struct Suite {
+    app: App,
+    owner: Addr,
+    user: Addr,
+    verifier: ReclaimVerifierContract,
+}
+
+impl Suite {
+    fn new(badge: Option<BadgeConfig>) -> Self {
+        let mut app = App::default();
+        let owner = app.api().addr_make("owner");
+        let user = app.api().addr_make("user");
+        let code = app.store_code(Box::new(
+            ContractWrapper::new(execute, instantiate, query).with_sudo(sudo),
+        ));
+        let msg = InstantiateMsg {
+            owner: owner.to_string(),
+            badge,
+            guardian: None,
+        };
+        let contract = app
+            .instantiate_contract(code, owner.clone(), &msg, &[], "reclaim", None)
+            .unwrap();
+        Suite {
+            app,
+            owner,
+            user,
+            verifier: ReclaimVerifierContract(contract),
+        }
+    }
+
+    fn add_epoch(&mut self, witnesses: &[TestWitness], minimum_witness: u128) -> AppResponse {
+        let witness = witnesses.iter().map(TestWitness::witness).collect();
+        let msg = self
+            .verifier
+            .add_epoch(
+                witness,
+                Uint128::new(minimum_witness),
+                EpochPolicy::default(),
+                None,
+            )
+            .unwrap();
+        self.app.execute(self.owner.clone(), msg).unwrap()
+    }
+
+    // A proof for the stored epoch `id`, signed by the witnesses selected at the current block
+    fn proof(&self, id: u128, witnesses: &[TestWitness]) -> Proof {
+        let epoch = self
+            .verifier
+            .epoch(&self.app.wrap(), id, None)
+            .unwrap()
+            .epoch;
+        test_proof(&epoch, witnesses, self.app.block_info().time)
+    }
+
+    fn verify(&mut self, proof: Proof) -> anyhow::Result<AppResponse> {
+        let msg = self
+            .verifier
+            .verify_proof(proof, None, None, vec![])
+            .unwrap();
+        self.app.execute(self.user.clone(), msg)
+    }
+}
+
+fn wasm_attribute(res: &AppResponse, key: &str) -> Option<String> {
+    res.events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .flat_map(|event| &event.attributes)
+        .find(|attribute| attribute.key == key)
+        .map(|attribute| attribute.value.clone())
+}
+
+fn signers(res: &AppResponse) -> Vec<String> {
+    res.events
+        .iter()
+        .filter(|event| event.ty == "wasm-signer")
+        .flat_map(|event| &event.attributes)
+        .filter(|attribute| attribute.key == "sig")
+        .map(|attribute| attribute.value.clone())
+        .collect()
+}
+
+#[test]
+fn epochs_are_created_and_rotated() {
+    let mut suite = Suite::new(None);
+    let first = test_witnesses(3);
+    let second: Vec<TestWitness> = (10..13).map(TestWitness::new).collect();
+
+    let res = suite.add_epoch(&first, 2);
+    assert_eq!(wasm_attribute(&res, "action").unwrap(), "add_epoch");
+    assert_eq!(wasm_attribute(&res, "epoch_id").unwrap(), "1");
+    let epoch = suite
+        .verifier
+        .epoch(&suite.app.wrap(), 1, None)
+        .unwrap()
+        .epoch;
+    assert_eq!(epoch.witness, test_epoch(1, &first, 2).witness);
+
+    let res = suite.add_epoch(&second, 2);
+    assert_eq!(wasm_attribute(&res, "epoch_id").unwrap(), "2");
+    let ids = suite.verifier.all_epochs(&suite.app.wrap(), None).unwrap();
+    assert_eq!(ids.ids, vec![1, 2]);
+
+    // proofs of both epochs verify until the first one is ended
+    let old_proof = suite.proof(1, &first);
+    let res = suite.verify(old_proof.clone()).unwrap();
+    assert_eq!(signers(&res).len(), old_proof.signedClaim.signatures.len());
+    suite.verify(suite.proof(2, &second)).unwrap();
+
+    suite
+        .app
+        .wasm_sudo(
+            suite.verifier.addr(),
+            &SudoMsg::EndEpoch {
+                id: 1,
+                domain: None,
+            },
+        )
+        .unwrap();
+    let err = suite.verify(old_proof).unwrap_err();
+    assert!(err.root_cause().to_string().contains("[21:epoch_ended]"));
+    suite.verify(suite.proof(2, &second)).unwrap();
+}
+
+#[test]
+fn rotated_witnesses_no_longer_sign() {
+    let mut suite = Suite::new(None);
+    let old = test_witnesses(2);
+    let new: Vec<TestWitness> = (20..22).map(TestWitness::new).collect();
+    suite.add_epoch(&old, 1);
+    let old_proof = suite.proof(1, &old);
+
+    let msg = suite
+        .verifier
+        .update_witnesses(
+            new.iter().map(TestWitness::witness).collect(),
+            Uint128::one(),
+            EpochPolicy::default(),
+            None,
+        )
+        .unwrap();
+    let res = suite.app.execute(suite.owner.clone(), msg).unwrap();
+    assert_eq!(wasm_attribute(&res, "action").unwrap(), "update_witnesses");
+
+    let err = suite.verify(old_proof).unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("[9:unexpected_signer]"));
+    let res = suite.verify(suite.proof(1, &new)).unwrap();
+    let signer = signers(&res).pop().unwrap();
+    assert!(new.iter().any(|witness| witness.address() == signer));
+}
+
+#[test]
+fn delayed_epoch_changes_wait_for_the_timelock() {
+    let mut suite = Suite::new(None);
+    let witnesses = test_witnesses(2);
+    let msg = suite.verifier.set_epoch_delay(60).unwrap();
+    suite.app.execute(suite.owner.clone(), msg).unwrap();
+
+    let res = suite.add_epoch(&witnesses, 1);
+    assert_eq!(
+        wasm_attribute(&res, "action").unwrap(),
+        "queue_epoch_change"
+    );
+    let id: u64 = wasm_attribute(&res, "pending_id").unwrap().parse().unwrap();
+    let pending = suite
+        .verifier
+        .pending_epoch_changes(&suite.app.wrap())
+        .unwrap();
+    assert_eq!(pending.changes.len(), 1);
+
+    let execute_change = suite.verifier.execute_epoch_change(id).unwrap();
+    let err = suite
+        .app
+        .execute(suite.user.clone(), execute_change.clone())
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("[20:timelock_not_expired]"));
+
+    suite
+        .app
+        .update_block(|block| block.time = block.time.plus_seconds(60));
+    let res = suite
+        .app
+        .execute(suite.user.clone(), execute_change)
+        .unwrap();
+    assert_eq!(wasm_attribute(&res, "epoch_id").unwrap(), "1");
+    suite.verify(suite.proof(1, &witnesses)).unwrap();
+}
+
+#[test]
+fn contracts_verify_proofs_for_their_users() {
+    let mut suite = Suite::new(Some(BadgeConfig {
+        extracted_parameters: vec!["username".to_string()],
+    }));
+    let witnesses = test_witnesses(4);
+    suite.add_epoch(&witnesses, 2);
+
+    let code = suite.app.store_code(Box::new(
+        ContractWrapper::new(caller::execute, caller::instantiate, caller::query)
+            .with_reply(caller::reply),
+    ));
+    let caller = suite
+        .app
+        .instantiate_contract(code, suite.owner.clone(), &Empty {}, &[], "caller", None)
+        .unwrap();
+
+    // the user owns the claim the calling contract submits
+    let epoch = suite
+        .verifier
+        .epoch(&suite.app.wrap(), 1, None)
+        .unwrap()
+        .epoch;
+    let time = suite.app.block_info().time;
+    let proof = owned_test_proof(&epoch, &witnesses, time, suite.user.as_str());
+    let msg = caller::ExecuteMsg::Verify {
+        verifier: suite.verifier.addr().to_string(),
+        proof: proof.clone(),
+    };
+    let res = suite
+        .app
+        .execute_contract(suite.user.clone(), caller.clone(), &msg, &[])
+        .unwrap();
+
+    // the signers seen by the calling contract come back as its response data
+    let data: Vec<String> = cosmwasm_std::from_json(res.data.as_ref().unwrap()).unwrap();
+    assert_eq!(data, signers(&res));
+    assert_eq!(data.len(), proof.signedClaim.signatures.len());
+
+    // the badge belongs to the claim owner, not to the contract that submitted the proof
+    let identifier = proof.signedClaim.claim.identifier;
+    let badges = suite
+        .verifier
+        .tokens(&suite.app.wrap(), suite.user.to_string(), None, None)
+        .unwrap();
+    assert_eq!(badges.tokens, vec![identifier.clone()]);
+    let badges = suite
+        .verifier
+        .tokens(&suite.app.wrap(), caller.to_string(), None, None)
+        .unwrap();
+    assert!(badges.tokens.is_empty());
+    let badge = suite
+        .verifier
+        .badge(&suite.app.wrap(), identifier)
+        .unwrap()
+        .badge;
+    assert_eq!(badge.extracted_parameters[0].value, "reclaim");
+
+    // a failing verification reverts the calling contract as well
+    let pause = suite.verifier.pause(PauseScope::Verification).unwrap();
+    suite.app.execute(suite.owner.clone(), pause).unwrap();
+    let err = suite
+        .app
+        .execute_contract(suite.user.clone(), caller, &msg, &[])
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("[18:paused]"));
+}
@@ -0,0 +1,71 @@
+//! Runs the `reclaim-verify` binary on the recorded SDK proof and checks its verdict and
+//! exit codes.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+const PROOF: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/sdk_proof.json");
+const EPOCH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/testdata/epoch.json");
+// a block time shortly after the recorded proof, not on a whole second
+const TIME_NANOS: &str = "1748539900123456789";
+
+fn reclaim_verify(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_reclaim-verify"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+// The recorded epoch with `from` replaced by `to`, in a file of its own
+fn edited_epoch(name: &str, from: &str, to: &str) -> PathBuf {
+    let epoch = std::fs::read_to_string(EPOCH).unwrap();
+    assert!(epoch.contains(from));
+    let path = std::env::temp_dir().join(format!("reclaim-verify-{}-{name}", std::process::id()));
+    std::fs::write(&path, epoch.replace(from, to)).unwrap();
+    path
+}
+
+#[test]
+fn passing_proof_exits_zero() {
+    let output = reclaim_verify(&[PROOF, EPOCH, "--time-nanos", TIME_NANOS]);
+    assert_eq!(output.status.code(), Some(0));
+    let printed = stdout(&output);
+    assert!(printed.contains(&format!("expected witnesses (at {TIME_NANOS} ns)")));
+    assert!(printed.contains("recovered signers:\n  0x244897572368eadf65bfbc5aec98d8e5443a9072"));
+    assert!(printed.ends_with("verdict: PASS\n"));
+
+    let output = reclaim_verify(&[PROOF, EPOCH, "--time", "1748539900"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert!(stdout(&output).contains("expected witnesses (at 1748539900000000000 ns)"));
+}
+
+#[test]
+fn failing_proof_exits_one() {
+    let epoch = edited_epoch("ended.json", r#""ended": false"#, r#""ended": true"#);
+    let output = reclaim_verify(&[PROOF, epoch.to_str().unwrap(), "--time-nanos", TIME_NANOS]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("verdict: FAIL\n  - epoch 1 has ended"));
+
+    let epoch = edited_epoch("other.json", "0x2448975", "0x0000000");
+    let output = reclaim_verify(&[PROOF, epoch.to_str().unwrap(), "--time-nanos", TIME_NANOS]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("[9:unexpected_signer]"));
+}
+
+#[test]
+fn unusable_input_exits_two() {
+    for args in [
+        vec![PROOF],
+        vec![PROOF, EPOCH, "--time-nanos", "soon"],
+        vec![PROOF, "missing.json"],
+        vec![EPOCH, EPOCH],
+    ] {
+        let output = reclaim_verify(&args);
+        assert_eq!(output.status.code(), Some(2), "{args:?}");
+        assert!(String::from_utf8_lossy(&output.stderr).contains("usage: reclaim-verify"));
+    }
+}